@@ -0,0 +1,150 @@
+use crate::physics::Controls;
+use macroquad::prelude::*;
+
+// below this speed the brake pedal selects reverse and the accelerator selects forward
+const GEAR_CHANGE_SPEED: f32 = 5.0;
+// how hard a pedal has to be pressed to change gear
+const GEAR_CHANGE_PEDAL: f32 = 0.5;
+
+// a raw request from the network or keyboard
+#[derive(Default, Clone, Copy)]
+pub struct Input {
+    pub min: f32,
+    pub weight: f32,
+    pub max: f32,
+    pub default: f32,
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Gear {
+    Forward,
+    Reverse,
+}
+
+// how quickly and how far the controls can move, per car class
+#[derive(Clone, Copy)]
+pub struct ActuatorLimits {
+    pub steer_slew: f32,    // steering travel per second
+    pub throttle_ramp: f32, // throttle travel per second
+    pub brake_ramp: f32,    // brake travel per second
+    pub max_speed: f32,
+    pub max_reverse_speed: f32, // zero means the car has no reverse gear
+}
+
+// sits between the inputs and the physics and turns requests into what the car actually does
+#[derive(Clone)]
+pub struct Actuators {
+    pub limits: ActuatorLimits,
+    pub gear: Gear,
+    throttle: f32,
+    steer: f32,
+    brake: f32,
+}
+
+impl Input {
+    pub fn new_default() -> Self {
+        Self {
+            min: 0.0,
+            weight: 0.0,
+            max: 1.0,
+            default: 0.0,
+        }
+    }
+
+    pub fn new_centred() -> Self {
+        Self {
+            min: -1.0,
+            weight: 0.0,
+            max: 1.0,
+            default: 0.0,
+        }
+    }
+
+    pub fn clamped(&self) -> f32 {
+        return clamp(self.weight, self.min, self.max);
+    }
+
+    pub fn reset(&mut self) {
+        self.weight = self.default;
+    }
+}
+
+impl Actuators {
+    pub fn new(limits: ActuatorLimits) -> Self {
+        Self {
+            limits,
+            gear: Gear::Forward,
+            throttle: 0.0,
+            steer: 0.0,
+            brake: 0.0,
+        }
+    }
+
+    pub fn apply(
+        &mut self,
+        accelerator: &Input,
+        steering: &Input,
+        brakes: &Input,
+        forward_speed: f32,
+        dt: f32,
+    ) -> Controls {
+        let target_throttle = accelerator.clamped();
+        let target_steer = steering.clamped();
+        let target_brake = brakes.clamped();
+
+        // move each actuator towards what was asked for, no faster than its rate limit
+        self.throttle = slew(
+            self.throttle,
+            target_throttle,
+            self.limits.throttle_ramp * dt,
+        );
+        self.steer = slew(self.steer, target_steer, self.limits.steer_slew * dt);
+        self.brake = slew(self.brake, target_brake, self.limits.brake_ramp * dt);
+
+        // gear changes only happen when the car has almost stopped
+        if forward_speed.abs() < GEAR_CHANGE_SPEED {
+            if self.gear == Gear::Forward
+                && self.limits.max_reverse_speed > 0.0
+                && target_brake > GEAR_CHANGE_PEDAL
+                && target_throttle < 1.0 - GEAR_CHANGE_PEDAL
+            {
+                self.gear = Gear::Reverse;
+            } else if self.gear == Gear::Reverse && target_throttle > GEAR_CHANGE_PEDAL {
+                self.gear = Gear::Forward;
+            }
+        }
+
+        match self.gear {
+            Gear::Forward => Controls {
+                throttle: self.throttle,
+                steer: self.steer,
+                brake: self.brake,
+            },
+            // in reverse the brake pedal drives backwards and the accelerator slows down
+            Gear::Reverse => Controls {
+                throttle: -self.brake,
+                steer: self.steer,
+                brake: self.throttle,
+            },
+        }
+    }
+
+    pub fn limit_speed(&self, velocity: Vec2) -> Vec2 {
+        let top_speed = match self.gear {
+            Gear::Forward => self.limits.max_speed,
+            Gear::Reverse => self.limits.max_reverse_speed,
+        };
+        return velocity.clamp_length_max(top_speed);
+    }
+
+    pub fn reset(&mut self) {
+        self.gear = Gear::Forward;
+        self.throttle = 0.0;
+        self.steer = 0.0;
+        self.brake = 0.0;
+    }
+}
+
+fn slew(current: f32, target: f32, max_change: f32) -> f32 {
+    return current + clamp(target - current, -max_change, max_change);
+}
@@ -1,109 +1,339 @@
-use crate::population::Population;
-use crate::{
-    ui::{Button, Slider},
-    WINDOW_HEIGHT, WINDOW_WIDTH,
-};
+use crate::camera::Camera;
+use crate::car::{CollisionMode, OffTrackRule};
+use crate::car_spec::{CarSpec, CAR_CONFIG_PATH};
+use crate::compare::RunComparison;
+use crate::config::SimConfig;
+use crate::drive::{DriveSession, Opponents};
+use crate::hall_of_fame::HallOfFame;
+use crate::islands::Archipelago;
+use crate::physics::PhysicsModel;
+use crate::replay_viewer::ReplayViewer;
+use crate::telemetry::TelemetryMode;
+use crate::telemetry_viewer::TelemetryViewer;
+use crate::track::Track;
+use crate::ui::{Button, Selector, Slider};
 use macroquad::prelude::*;
 
 #[derive(PartialEq)]
 enum ProgramStatus {
     MainMenu,
     Simulation,
+    Drive,
+    Replay,
+    HallOfFame,
+    Compare,
+    Telemetry,
 }
 
 pub struct App {
     status: ProgramStatus,
-    population: Option<Population>,
+    population: Option<Archipelago>, // one population, or several as islands
+    drive: Option<DriveSession>,
+    replay_viewer: Option<ReplayViewer>,
+    hall_of_fame: Option<HallOfFame>,
+    compare: Option<RunComparison>,
+    telemetry_viewer: Option<TelemetryViewer>,
+    camera: Option<Camera>, // views whichever simulation is running
 
     // ui elements
     buttons: Vec<Button>,
     sliders: Vec<Slider>,
+    selectors: Vec<Selector>,
+    car_specs: Vec<CarSpec>,
     end_button: Option<Button>, // new line
 }
 
+impl Default for App {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl App {
     pub fn new() -> Self {
+        let car_specs = CarSpec::load_all(CAR_CONFIG_PATH);
+
+        // one option per class plus racing them all together
+        let mut class_names: Vec<&str> = car_specs.iter().map(|s| s.name.as_str()).collect();
+        class_names.push("Mixed");
+        let default_class = car_specs.iter().position(|s| s.name == "GT").unwrap_or(0);
+        let class_selector = Selector::new(850.0, 245.0, 250.0, 40.0, &class_names, default_class);
+
         Self {
             status: ProgramStatus::MainMenu,
             population: None,
+            drive: None,
+            replay_viewer: None,
+            hall_of_fame: None,
+            compare: None,
+            telemetry_viewer: None,
+            camera: None,
 
             // ui design
-            buttons: vec![Button::new(
-                400.0,
-                550.0,
-                400.0,
-                150.0,
-                "Run".to_string(),
-                LIGHTGRAY,
-            )],
+            buttons: vec![
+                Button::new(400.0, 550.0, 400.0, 150.0, "Run".to_string(), LIGHTGRAY),
+                Button::new(850.0, 550.0, 250.0, 70.0, "Drive".to_string(), LIGHTGRAY),
+                Button::new(850.0, 630.0, 250.0, 70.0, "Replays".to_string(), LIGHTGRAY),
+                Button::new(850.0, 710.0, 250.0, 70.0, "Records".to_string(), LIGHTGRAY),
+                Button::new(100.0, 550.0, 250.0, 70.0, "Compare".to_string(), LIGHTGRAY),
+                Button::new(
+                    100.0,
+                    630.0,
+                    250.0,
+                    70.0,
+                    "Telemetry".to_string(),
+                    LIGHTGRAY,
+                ),
+            ],
 
             end_button: None, // set to none by default
 
+            car_specs,
+
             sliders: vec![
                 Slider::new(600.0, 350.0, 10, 300, 220),
                 Slider::new(600.0, 450.0, 500, 3000, 1250),
             ],
+
+            selectors: vec![
+                Selector::new(330.0, 190.0, 250.0, 40.0, &["Any corner", "Centre"], 0),
+                Selector::new(850.0, 190.0, 250.0, 40.0, &["Eliminate", "Walls"], 0),
+                Selector::new(
+                    330.0,
+                    245.0,
+                    250.0,
+                    40.0,
+                    &["Arcade", "Kinematic", "Dynamic"],
+                    0,
+                ),
+                class_selector,
+                Selector::new(
+                    330.0,
+                    300.0,
+                    250.0,
+                    40.0,
+                    &["None", "Champion", "Population"],
+                    0,
+                ),
+                Selector::new(850.0, 300.0, 250.0, 40.0, &["Off", "On"], 0),
+                Selector::new(850.0, 380.0, 250.0, 40.0, &["Off", "Terminal"], 0),
+                Selector::new(
+                    330.0,
+                    380.0,
+                    250.0,
+                    40.0,
+                    &["Off", "Fastest laps", "Selected"],
+                    0,
+                ),
+                Selector::new(100.0, 740.0, 250.0, 40.0, &["Off", "On"], 0),
+                Selector::new(330.0, 490.0, 250.0, 40.0, &["1", "2", "3", "4"], 0),
+            ],
+        }
+    }
+
+    fn read_config(&self) -> SimConfig {
+        // build the settings from the main menu ui
+        SimConfig {
+            population_size: self.sliders[0].value as usize,
+            time_limit: self.sliders[1].value as u32,
+            off_track_rule: match self.selectors[0].index {
+                0 => OffTrackRule::AnyCorner,
+                _ => OffTrackRule::Centre,
+            },
+            collision_mode: match self.selectors[1].index {
+                0 => CollisionMode::Eliminate,
+                _ => CollisionMode::Walls,
+            },
+            physics_model: match self.selectors[2].index {
+                0 => PhysicsModel::Arcade,
+                1 => PhysicsModel::Kinematic,
+                _ => PhysicsModel::Dynamic,
+            },
+            car_classes: match self.car_specs.get(self.selectors[3].index) {
+                Some(spec) => vec![spec.clone()],
+                None => self.car_specs.clone(), // the last option is mixed
+            },
+            seed: macroquad::miniquad::date::now() as u64,
+            record_replays: self.selectors[5].index == 1,
+            log_laps: self.selectors[6].index == 1,
+            telemetry: match self.selectors[7].index {
+                0 => TelemetryMode::Off,
+                1 => TelemetryMode::FastestLaps,
+                _ => TelemetryMode::Selected,
+            },
+            reinject_elites: self.selectors[8].index == 1,
+            islands: self.selectors[9].index + 1,
+            ..SimConfig::default()
+        }
+    }
+
+    fn active_track(&self) -> Option<&Track> {
+        if let Some(pop) = &self.population {
+            return Some(pop.get_track());
+        }
+        if let Some(drive) = &self.drive {
+            return Some(drive.get_track());
+        }
+        if let Some(viewer) = &self.replay_viewer {
+            return Some(viewer.get_track());
+        }
+        return None;
+    }
+
+    fn leave_menu(&mut self, status: ProgramStatus) {
+        // start with the whole track in view, following the player when driving
+        let follow = status == ProgramStatus::Drive;
+        self.camera = self
+            .active_track()
+            .map(|track| Camera::new(track.get_bounds(), follow));
+        self.status = status;
+
+        // create the end simulation button
+        // button width is 150px height is 75px, it's moved to the corner every frame
+        self.end_button = Some(Button::new(0.0, 0.0, 150.0, 75.0, "End".to_string(), RED));
+
+        // reset the UI components
+        for b in self.buttons.iter_mut() {
+            b.reset();
+        }
+        for s in self.sliders.iter_mut() {
+            s.reset();
+        }
+        for s in self.selectors.iter_mut() {
+            s.reset();
         }
     }
 
     pub fn update(&mut self) {
-        if self.status == ProgramStatus::MainMenu {
-            // updates for main menu instance
-            for b in &mut self.buttons {
-                b.check_pressed();
-            }
-            for s in &mut self.sliders {
-                s.update();
-            }
-
-            // if the first button is pressed, (aka the 'Run' button)
-            if self.buttons[0].pressed {
-                self.status = ProgramStatus::Simulation;
-                // create population
-                let pop_size = self.sliders[0].value;
-                let gen_length = self.sliders[1].value;
-
-                self.population = Some(Population::new(pop_size as usize, gen_length as u32));
-
-                // create the end simulation button
-                // button width is 150px height is 75px
-                self.end_button = Some(Button::new(
-                    WINDOW_WIDTH as f32 - 150.0,
-                    WINDOW_HEIGHT as f32 - 150.0, // shift up 150px from bottom rather than 75px
-                    150.0,
-                    75.0,
-                    "End".to_string(),
-                    RED,
-                ));
-
-                // reset the UI components
-                for b in self.buttons.iter_mut() {
-                    b.reset();
+        match self.status {
+            ProgramStatus::MainMenu => {
+                // updates for main menu instance
+                for b in &mut self.buttons {
+                    b.check_pressed();
                 }
-                for s in self.sliders.iter_mut() {
-                    s.reset();
+                for s in &mut self.sliders {
+                    s.update();
+                }
+                for s in &mut self.selectors {
+                    s.update();
                 }
-            }
-        } else {
-            // run the simulation
 
-            if let Some(pop) = &mut self.population {
-                pop.update();
+                // if the first button is pressed, (aka the 'Run' button)
+                if self.buttons[0].pressed {
+                    // create population, staying on the menu if it can't be made
+                    self.population = Archipelago::new(self.read_config());
+                    match self.population {
+                        Some(_) => self.leave_menu(ProgramStatus::Simulation),
+                        None => self.buttons[0].reset(),
+                    }
+                } else if self.buttons[1].pressed {
+                    let opponents = match self.selectors[4].index {
+                        0 => Opponents::None,
+                        1 => Opponents::Champion,
+                        _ => Opponents::Population,
+                    };
+                    // stays on the menu if the track can't be loaded
+                    self.drive = DriveSession::new(self.read_config(), opponents);
+                    match self.drive {
+                        Some(_) => self.leave_menu(ProgramStatus::Drive),
+                        None => self.buttons[1].reset(),
+                    }
+                } else if self.buttons[2].pressed {
+                    // stays on the menu if there's nothing to watch
+                    self.replay_viewer = ReplayViewer::new();
+                    match self.replay_viewer {
+                        Some(_) => self.leave_menu(ProgramStatus::Replay),
+                        None => self.buttons[2].reset(),
+                    }
+                } else if self.buttons[3].pressed {
+                    self.hall_of_fame = Some(HallOfFame::new());
+                    self.leave_menu(ProgramStatus::HallOfFame);
+                } else if self.buttons[4].pressed {
+                    self.compare = Some(RunComparison::new());
+                    self.leave_menu(ProgramStatus::Compare);
+                } else if self.buttons[5].pressed {
+                    self.telemetry_viewer = Some(TelemetryViewer::new());
+                    self.leave_menu(ProgramStatus::Telemetry);
+                }
             }
+            ProgramStatus::Simulation => {
+                // run the simulation
+                if let Some(pop) = &mut self.population {
+                    if let Some(camera) = &self.camera {
+                        pop.update_selection(camera);
+                    }
+                    pop.update();
+                }
+            }
+            ProgramStatus::Drive => {
+                if let Some(drive) = &mut self.drive {
+                    drive.update();
+                }
+            }
+            ProgramStatus::Replay => {
+                if let (Some(viewer), Some(camera)) = (&mut self.replay_viewer, &self.camera) {
+                    viewer.update(camera);
+                }
+            }
+            ProgramStatus::HallOfFame => {
+                if let Some(hall_of_fame) = &mut self.hall_of_fame {
+                    hall_of_fame.update();
+                }
+            }
+            ProgramStatus::Compare => {
+                if let Some(compare) = &mut self.compare {
+                    compare.update();
+                }
+            }
+            ProgramStatus::Telemetry => {
+                if let Some(viewer) = &mut self.telemetry_viewer {
+                    viewer.update();
+                }
+            }
+        }
 
-            // update the end button
-            if let Some(end_button) = &mut self.end_button {
-                end_button.check_pressed();
+        // move the camera after the cars so it doesn't lag a frame behind
+        let follow_target = match self.status {
+            ProgramStatus::MainMenu
+            | ProgramStatus::HallOfFame
+            | ProgramStatus::Compare
+            | ProgramStatus::Telemetry => None,
+            ProgramStatus::Simulation => self
+                .population
+                .as_ref()
+                .and_then(|pop| pop.get_best_car())
+                .map(|car| car.get_centre()),
+            ProgramStatus::Drive => self.drive.as_ref().map(|drive| drive.get_player_pos()),
+            ProgramStatus::Replay => self
+                .replay_viewer
+                .as_ref()
+                .and_then(|viewer| viewer.get_followed_pos()),
+        };
+        if let Some(camera) = &mut self.camera {
+            camera.update(follow_target);
+        }
 
-                if end_button.pressed {
-                    self.population = None;
-                    self.status = ProgramStatus::MainMenu;
+        // update the end button
+        if let Some(end_button) = &mut self.end_button {
+            // keep it in the bottom corner when the window is resized
+            // shift up 150px from bottom rather than 75px
+            end_button.set_position(screen_width() - 150.0, screen_height() - 150.0);
+            end_button.check_pressed();
 
-                    // reset the ui components
-                    self.end_button.as_mut().unwrap().reset();
+            if end_button.pressed {
+                self.population = None;
+                self.drive = None;
+                self.replay_viewer = None;
+                self.hall_of_fame = None;
+                self.compare = None;
+                self.telemetry_viewer = None;
+                self.camera = None;
+                self.status = ProgramStatus::MainMenu;
 
-                    self.end_button = None;
-                }
+                // reset the ui components
+                self.end_button.as_mut().unwrap().reset();
+
+                self.end_button = None;
             }
         }
     }
@@ -119,8 +349,21 @@ impl App {
             for s in &self.sliders {
                 s.draw();
             }
+            for s in &self.selectors {
+                s.draw();
+            }
 
             // draw the labels for the ui elements
+            draw_text("Off Track Rule:", 100.0, 218.0, 30.0, BLACK);
+            draw_text("Track Edges:", 620.0, 218.0, 30.0, BLACK);
+            draw_text("Physics:", 100.0, 273.0, 30.0, BLACK);
+            draw_text("Car Class:", 620.0, 273.0, 30.0, BLACK);
+            draw_text("Drive Against:", 100.0, 328.0, 30.0, BLACK);
+            draw_text("Record Replays:", 620.0, 328.0, 30.0, BLACK);
+            draw_text("Lap Log:", 620.0, 408.0, 30.0, BLACK);
+            draw_text("Telemetry:", 100.0, 408.0, 30.0, BLACK);
+            draw_text("Re-race Elites:", 100.0, 730.0, 25.0, BLACK);
+            draw_text("Islands:", 100.0, 518.0, 30.0, BLACK);
             draw_text("Population Size:", 100.0, 350.0, 30.0, BLACK);
             draw_text("Generation Time Limit:", 100.0, 450.0, 30.0, BLACK);
             draw_text("(Ticks)", 140.0, 475.0, 18.0, BLACK);
@@ -128,8 +371,39 @@ impl App {
         } else {
             // inside a simulation so draw it!
 
+            // the world goes through the camera
+            if let Some(camera) = &self.camera {
+                camera.set_active();
+            }
+            if let Some(pop) = &self.population {
+                pop.draw_world();
+            }
+            if let Some(drive) = &self.drive {
+                drive.draw_world();
+            }
+            if let Some(viewer) = &self.replay_viewer {
+                viewer.draw_world();
+            }
+
+            // the ui is drawn in screen pixels on top
+            set_default_camera();
             if let Some(pop) = &self.population {
-                pop.draw();
+                pop.draw_hud();
+            }
+            if let Some(drive) = &self.drive {
+                drive.draw_hud();
+            }
+            if let Some(viewer) = &self.replay_viewer {
+                viewer.draw_hud();
+            }
+            if let Some(hall_of_fame) = &self.hall_of_fame {
+                hall_of_fame.draw();
+            }
+            if let Some(compare) = &self.compare {
+                compare.draw();
+            }
+            if let Some(viewer) = &self.telemetry_viewer {
+                viewer.draw();
             }
 
             // draw the end button
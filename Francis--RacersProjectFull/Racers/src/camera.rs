@@ -0,0 +1,110 @@
+use macroquad::prelude::*;
+
+const MIN_ZOOM: f32 = 0.1;
+const MAX_ZOOM: f32 = 8.0;
+const ZOOM_STEP: f32 = 1.1; // per click of the mouse wheel
+
+// how much of the screen the track fills when the view is reset
+const FIT_MARGIN: f32 = 0.95;
+// fraction of the gap to the followed car closed each frame
+const FOLLOW_SMOOTHING: f32 = 0.15;
+
+// turns world coordinates into screen pixels
+// scroll to zoom, drag with the right mouse button to pan, F to follow, Home to reset
+pub struct Camera {
+    pub target: Vec2, // world point in the middle of the screen
+    pub zoom: f32,    // screen pixels per world unit
+    pub follow: bool,
+    home: Rect, // the area shown when the view is reset
+    drag_start: Option<Vec2>,
+}
+
+impl Camera {
+    pub fn new(home: Rect, follow: bool) -> Self {
+        let mut camera = Self {
+            target: home.center(),
+            zoom: 1.0,
+            follow,
+            home,
+            drag_start: None,
+        };
+        camera.reset();
+        return camera;
+    }
+
+    pub fn reset(&mut self) {
+        // fit the whole area on the screen
+        self.target = self.home.center();
+        self.zoom = (screen_width() / self.home.w).min(screen_height() / self.home.h) * FIT_MARGIN;
+    }
+
+    pub fn update(&mut self, follow_target: Option<Vec2>) {
+        if is_key_pressed(KeyCode::F) {
+            self.follow = !self.follow;
+        }
+        if is_key_pressed(KeyCode::Home) {
+            self.follow = false;
+            self.reset();
+        }
+
+        // zoom towards the mouse so the point under it stays still
+        let (_, wheel) = mouse_wheel();
+        if wheel != 0.0 {
+            let mouse = Vec2::from(mouse_position());
+            let before = self.screen_to_world(mouse);
+            let factor = match wheel > 0.0 {
+                true => ZOOM_STEP,
+                false => 1.0 / ZOOM_STEP,
+            };
+            self.zoom = clamp(self.zoom * factor, MIN_ZOOM, MAX_ZOOM);
+            if !self.follow {
+                self.target += before - self.screen_to_world(mouse);
+            }
+        }
+
+        // dragging takes over from following
+        let mouse = Vec2::from(mouse_position());
+        if is_mouse_button_pressed(MouseButton::Right) {
+            self.drag_start = Some(mouse);
+            self.follow = false;
+        }
+        if let Some(last) = self.drag_start {
+            self.target -= (mouse - last) / self.zoom;
+            self.drag_start = Some(mouse);
+        }
+        if is_mouse_button_released(MouseButton::Right) {
+            self.drag_start = None;
+        }
+
+        if self.follow {
+            if let Some(point) = follow_target {
+                self.target += (point - self.target) * FOLLOW_SMOOTHING;
+            }
+        }
+    }
+
+    pub fn get_camera2d(&self) -> Camera2D {
+        // macroquad flips y when drawing to the screen so both zooms stay positive
+        Camera2D {
+            target: self.target,
+            zoom: vec2(
+                2.0 * self.zoom / screen_width(),
+                2.0 * self.zoom / screen_height(),
+            ),
+            ..Default::default()
+        }
+    }
+
+    pub fn screen_to_world(&self, point: Vec2) -> Vec2 {
+        return self.get_camera2d().screen_to_world(point);
+    }
+
+    pub fn world_to_screen(&self, point: Vec2) -> Vec2 {
+        return self.get_camera2d().world_to_screen(point);
+    }
+
+    // everything drawn after this is in world coordinates
+    pub fn set_active(&self) {
+        set_camera(&self.get_camera2d());
+    }
+}
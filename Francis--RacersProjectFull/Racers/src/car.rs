@@ -1,16 +1,18 @@
+use crate::actuator::{Actuators, Input};
+use crate::car_spec::{CarClass, CarSpec};
+use crate::lap_check::*;
+use crate::leaderboard::LapEntry;
+use crate::lineage::GenomeId;
 use crate::network::*;
-use crate::utils::{find_line_eq, lerp, line_intersection, to_rad};
+use crate::physics::{self, Controls, PhysicsModel, VehicleState};
+use crate::telemetry::TelemetryFrame;
+use crate::utils::lerp;
 use core::f32;
 use macroquad::prelude::*;
 use macroquad::texture::Texture2D;
 use std::f32::consts::PI;
 
 use crate::track::Track;
-use crate::{WINDOW_HEIGHT, WINDOW_WIDTH};
-
-// consts
-const FRIC_COEF: f32 = 0.88;
-const LAT_FRIC_COEF: f32 = 0.05;
 
 // fitness constannt
 const SECTOR_BONUS: i32 = 1000;
@@ -20,14 +22,27 @@ const SECTOR_SPEED_MULTIPLIER: i32 = 50000;
 const BACK_SECTOR_PUNISHMENT: i32 = -1000;
 const BACK_LAP_PUNISHMENT: i32 = -5000;
 const CRASH_PUNISHMENT: i32 = -10000;
+const WALL_PUNISHMENT: i32 = -300;
+const INVALID_LAP_PUNISHMENT: i32 = -LAP_BONUS; // cutting the track earns nothing
+
+// wall collisions
+const WALL_RESTITUTION: f32 = 0.3; // how much of the speed into the wall bounces back
+const WALL_SPEED_LOSS: f32 = 0.7; // speed kept along the wall after a hit
 
 // ai settings
 const NUM_RAYS: usize = 15;
 const AI_FOV: f32 = 200.0;
+pub const NUM_INPUTS: usize = 6 + NUM_RAYS;
+pub const NUM_OUTPUTS: usize = 3; // throttle, steer and brake
+                                  // how far the rays can see, the distances are given to the network as a fraction of this
+const SENSOR_RANGE: f32 = 1200.0;
 
 // colours
 const TRANSPARENT_COLOUR: Color = color_u8!(255, 255, 255, 50);
 
+// where the car was on one tick, (centre, angle)
+pub type TraceFrame = (Vec2, f32);
+
 #[derive(Clone)]
 pub struct Car {
     // Physics variables
@@ -39,23 +54,37 @@ pub struct Car {
     // -- Scalar
     angle: f32,
     steer: f32,
+    yaw_rate: f32,
 
     // Graphics
-    texture: Texture2D,
+    texture: Option<Texture2D>, // None when training without a window
     rect: Rect,
 
+    // the class of car, sets the size, engine and grip
+    pub spec: CarSpec,
+
     // network
     pub brain: Network,
+    pub controller: Controller,
 
     // inputs for controllers
     accelerator_input: Input,
     steering_input: Input, // radians
     brakes_input: Input,
+    actuators: Actuators,
+    controls: Controls,
 
     // others
     pub crashed: bool,
+    pub wall_hits: usize,
+    touching_wall: bool,
+
+    // lap validity
+    lap_check: LapCheck,
+    pub last_lap_invalid: Option<LapInvalid>, // None if the last lap counted
     pub fitness: i32,
     pub number: usize,
+    pub genome_id: GenomeId, // set by the population, see Lineage
     pub just_lapped: bool,
 
     // stats
@@ -63,44 +92,67 @@ pub struct Car {
     timer: i32, // sector timer
     prev_checkpoint: usize,
     pub laps: usize,
-    pub lap_time: usize, // keeps track of current lap time
-    lap_timer: usize,    // times the lap rather than sector
+    pub lap_time: usize,  // keeps track of current lap time
+    lap_timer: usize,     // times the lap rather than sector
+    pub lap_seconds: f32, // the last lap in simulated seconds, ticks depend on the frame rate
+    lap_clock: f32,
+    sector_clock: f32,
+    pub best_lap_seconds: Option<f32>, // fastest valid lap this generation
+    pub distance: f32,                 // how far the car has driven this generation
+    lap_sectors: Vec<f32>,             // splits of the lap so far in seconds
+    pub last_lap_sectors: Vec<f32>,    // splits of the last full lap, one per sector
+    lap_trace: Vec<TraceFrame>,
+    last_lap_trace: Vec<TraceFrame>,
+    trail: Vec<Vec2>,   // every position since the car was made or reset
+    pub trail_on: bool, // off for cars that are never reset, so the trail can't grow forever
+
+    // tick by tick logging, only kept when turned on
+    pub telemetry_on: bool,
+    telemetry_waiting: bool, // turned on part way round, recording starts with the next lap
+    lap_telemetry: Vec<TelemetryFrame>,
+    last_lap_telemetry: Vec<TelemetryFrame>,
+}
+
+// who is driving the car
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Controller {
+    Network,
+    Keyboard,
 }
 
-#[derive(Default, Clone, Copy)]
-pub struct Input {
-    min: f32,
-    weight: f32,
-    max: f32,
-    default: f32,
+// what happens when a car leaves the track
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum CollisionMode {
+    Eliminate, // the car is out for the rest of the generation
+    Walls,     // the edges are solid and the car bounces off them
+}
+
+// decides when a car counts as having left the track
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum OffTrackRule {
+    AnyCorner, // any corner of the hitbox over the edge
+    Centre,    // the centre of the car over the edge
 }
 
 impl Car {
-    pub const HITBOX_WIDTH: f32 = 30.0;
-    pub const HITBOX_HEIGHT: f32 = 60.0;
-    pub const MAX_SPEED: f32 = 350.0;
-    pub const MAX_TURNING_ANGLE: f32 = (40.0 / 180.0) * PI;
-    pub const MAX_ACC: f32 = 400.0;
-    pub const STEER_WEIGHT: f32 = PI / 6.0;
-    pub const MASS: f32 = 40.0;
-    pub const BRAKING_FACTOR: f32 = 0.9;
-
-    pub fn new(start_pos: Vec2, number: usize) -> Self {
+    pub fn new(start_pos: Vec2, number: usize, class: &CarClass) -> Self {
+        let spec = class.spec.clone();
+
         // default car setup
         let mut brain = Network::new_empty();
         brain = brain
-            .add_layer(Layer::new_random(6 + NUM_RAYS, 12, None))
+            .add_layer(Layer::new_random(NUM_INPUTS, 12, None))
             .add_layer(Layer::new_random(12, 8, None))
             .add_layer(Layer::new_random(8, 5, None))
-            .add_layer(Layer::new_random(5, 3, Some(sigmoid)));
+            .add_layer(Layer::new_random(5, NUM_OUTPUTS, Some(sigmoid)));
 
         let mut car: Self = Self {
-            texture: Texture2D::from_file_with_format(include_bytes!("../assets/car.png"), None),
+            texture: class.texture.clone(),
 
             // Defining Vector
             position: vec2(
-                start_pos.x - Self::HITBOX_WIDTH / 2.0,
-                start_pos.y - Self::HITBOX_HEIGHT / 2.0,
+                start_pos.x - spec.width / 2.0,
+                start_pos.y - spec.length / 2.0,
             ),
             velocity: Vec2::ZERO,
             direction: Vec2::ZERO,
@@ -109,26 +161,30 @@ impl Car {
             // Scalar
             angle: 0.0,
             steer: 0.0,
+            yaw_rate: 0.0,
 
             // other
-            rect: Rect::new(0.0, 0.0, Car::HITBOX_WIDTH, Car::HITBOX_HEIGHT),
+            rect: Rect::new(0.0, 0.0, spec.width, spec.length),
 
             // brain
             brain,
+            controller: Controller::Network,
 
             // inputs
             accelerator_input: Input::new_default(),
             brakes_input: Input::new_default(),
-            steering_input: Input {
-                min: -1.0,
-                max: 1.0,
-                weight: 0.0,
-                default: 0.0,
-            },
+            steering_input: Input::new_centred(),
+            actuators: Actuators::new(spec.limits),
+            controls: Controls::default(),
 
             crashed: false,
+            wall_hits: 0,
+            touching_wall: false,
+            lap_check: LapCheck::new(start_pos),
+            last_lap_invalid: None,
             fitness: 0,
             number,
+            genome_id: 0,
             just_lapped: false,
 
             // stats
@@ -138,13 +194,34 @@ impl Car {
             laps: 0,
             lap_timer: 0,
             lap_time: 0,
+            lap_seconds: 0.0,
+            best_lap_seconds: None,
+            distance: 0.0,
+            lap_clock: 0.0,
+            sector_clock: 0.0,
+            lap_sectors: vec![],
+            last_lap_sectors: vec![],
+            lap_trace: vec![],
+            last_lap_trace: vec![],
+            trail: vec![],
+            trail_on: true,
+            telemetry_on: false,
+            telemetry_waiting: false,
+            lap_telemetry: vec![],
+            last_lap_telemetry: vec![],
+
+            spec,
         };
         car.direction = Vec2::from_angle(car.angle);
+        car.update_pos(car.position.x, car.position.y); // puts the hitbox on the start line
         return car;
     }
 
     pub fn draw(&self, best: bool) {
-        let mut draw_colour = WHITE;
+        let Some(texture) = &self.texture else {
+            return;
+        };
+        let mut draw_colour = self.spec.tint;
         if (self.crashed) {
             draw_colour = TRANSPARENT_COLOUR;
         }
@@ -165,7 +242,7 @@ impl Car {
             rotation: self.angle + PI / 2.0,
             pivot: None,
         };
-        draw_texture_ex(&self.texture, x, y, draw_colour, params);
+        draw_texture_ex(texture, x, y, draw_colour, params);
     }
 
     fn toll_fitness(&mut self, track: &Track) {
@@ -177,6 +254,7 @@ impl Car {
         }
 
         self.cumulative_speed += self.velocity.length();
+        self.lap_check.cross_checkpoints(track, self.rect.center());
         let sector = self.get_sector(track);
         let last_sector = track.get_points().len() - 1;
 
@@ -190,6 +268,8 @@ impl Car {
 
             self.fitness += SECTOR_BONUS + speed_bonus;
             self.timer = 0;
+            self.lap_sectors.push(self.sector_clock);
+            self.sector_clock = 0.0;
         } else if (sector == 0 && self.prev_checkpoint == last_sector) {
             // done a lap
             let sector_time = self.timer;
@@ -204,6 +284,23 @@ impl Car {
             self.timer = 0;
             self.lap_time = self.lap_timer;
             self.lap_timer = 0;
+            self.lap_seconds = self.lap_clock;
+            self.lap_clock = 0.0;
+            self.lap_sectors.push(self.sector_clock);
+            self.sector_clock = 0.0;
+            self.last_lap_sectors = std::mem::take(&mut self.lap_sectors);
+            self.last_lap_invalid = self.lap_check.finish_lap(track);
+            if self.last_lap_invalid.is_some() {
+                self.fitness += INVALID_LAP_PUNISHMENT;
+            } else if self
+                .best_lap_seconds
+                .is_none_or(|best| self.lap_seconds < best)
+            {
+                self.best_lap_seconds = Some(self.lap_seconds);
+            }
+            self.last_lap_trace = std::mem::take(&mut self.lap_trace);
+            self.last_lap_telemetry = std::mem::take(&mut self.lap_telemetry);
+            self.telemetry_waiting = false;
             self.just_lapped = true;
             self.laps += 1;
         } else {
@@ -214,11 +311,16 @@ impl Car {
                 self.timer = 0;
                 self.prev_checkpoint = last_sector;
                 self.fitness += BACK_LAP_PUNISHMENT; // DONT GO BACKWARDS
+                self.sector_clock = 0.0;
+                self.lap_sectors.clear();
             } else if sector < self.prev_checkpoint as i32 {
                 // going backwards
                 self.timer = 0;
                 self.prev_checkpoint = sector as usize;
                 self.fitness += BACK_SECTOR_PUNISHMENT;
+                // the splits after the sector it went back to have to be driven again
+                self.lap_sectors.truncate(sector as usize);
+                self.sector_clock = 0.0;
             }
         }
     }
@@ -238,29 +340,92 @@ impl Car {
 
     pub fn update_pos(&mut self, x: f32, y: f32) {
         // way to safely change position
-        let x = clamp(x, 0.0, WINDOW_WIDTH as f32 - Car::HITBOX_WIDTH); // keep the car on the screen
-        let y = clamp(y, 0.0, WINDOW_HEIGHT as f32 - Car::HITBOX_HEIGHT);
-
+        // the track limits keep cars in the world so there's no clamping to the screen
         self.position = Vec2::new(x, y);
         self.rect.x = x;
         self.rect.y = y;
     }
 
-    pub fn update(&mut self, track: &Track, dt: f32) {
+    pub fn update(&mut self, track: &Track, dt: f32, model: PhysicsModel) {
         self.just_lapped = false;
+        let fitness_before = self.fitness;
         self.toll_fitness(track);
 
-        if self.crashed == true {
+        if self.crashed {
             return;
         }
 
-        // run the neural network with inputs
+        let recording = self.telemetry_on && !self.telemetry_waiting;
+        let mut rays = vec![];
+        match self.controller {
+            Controller::Network => {
+                // run the network
+                let inputs = self.sense(track);
+                if recording {
+                    rays = inputs[..NUM_RAYS].iter().map(|r| *r as f32).collect();
+                }
+                let outputs = self.brain.run(inputs);
+                self.accelerator_input.weight = outputs[0] as f32;
+                self.steering_input.weight = ((outputs[1] - 0.5) * 2.0) as f32; // convert to value between -1.0 and 1.0
+                self.brakes_input.weight = outputs[2] as f32;
+            }
+            Controller::Keyboard => self.keyboard_control(),
+        }
+
+        self.drive(dt, model);
+
+        if recording {
+            if rays.is_empty() {
+                rays = self.cast_rays(NUM_RAYS, AI_FOV, track);
+            }
+            self.record_telemetry(&rays, self.fitness - fitness_before, dt);
+        }
+    }
+
+    fn record_telemetry(&mut self, rays: &[f32], fitness_delta: i32, dt: f32) {
+        let distance = self.lap_telemetry.last().map_or(0.0, |f| f.distance);
+        self.lap_telemetry.push(TelemetryFrame {
+            time: self.lap_clock,
+            distance: distance + self.velocity.length() * dt,
+            position: self.rect.center(),
+            velocity: self.velocity,
+            angle: self.angle,
+            controls: self.controls,
+            sector: self.prev_checkpoint,
+            fitness_delta,
+            rays: rays.iter().map(|r| r * SENSOR_RANGE).collect(),
+        });
+    }
+
+    // starts or stops logging, a lap that has already started isn't logged
+    // so every saved lap runs from the finish line
+    pub fn set_telemetry(&mut self, on: bool) {
+        if on == self.telemetry_on {
+            return;
+        }
+        self.telemetry_on = on;
+        self.telemetry_waiting = on && self.lap_clock > 0.0;
+        self.lap_telemetry.clear();
+    }
+
+    // logging is on but won't start until the car next crosses the finish line
+    pub fn is_telemetry_waiting(&self) -> bool {
+        return self.telemetry_on && self.telemetry_waiting;
+    }
+
+    // the frames of the lap that just finished, taken so they aren't kept twice
+    pub fn take_last_lap_telemetry(&mut self) -> Vec<TelemetryFrame> {
+        return std::mem::take(&mut self.last_lap_telemetry);
+    }
+
+    pub fn sense(&self, track: &Track) -> Vec<f64> {
+        // the inputs the network sees
         let rays = self.cast_rays(NUM_RAYS, AI_FOV, track);
-        let velx_norm = self.velocity.x / Car::MAX_SPEED;
-        let vely_norm = self.velocity.y / Car::MAX_SPEED;
-        let accx_norm = self.acceleration.x / Car::MAX_ACC;
-        let accy_norm = self.acceleration.y / Car::MAX_ACC;
-        let steer_norm = self.steer / Car::STEER_WEIGHT;
+        let velx_norm = self.velocity.x / self.spec.limits.max_speed;
+        let vely_norm = self.velocity.y / self.spec.limits.max_speed;
+        let accx_norm = self.acceleration.x / self.spec.max_acc();
+        let accy_norm = self.acceleration.y / self.spec.max_acc();
+        let steer_norm = self.steer / self.spec.steer_weight;
         let angle_norm = (self.angle).sin();
 
         let mut inputs: Vec<f64> = vec![];
@@ -274,45 +439,153 @@ impl Car {
         inputs.push(steer_norm as f64);
         inputs.push(angle_norm as f64);
 
-        // run the network
-        let outputs = self.brain.run(inputs);
-        self.accelerator_input.weight = outputs[0] as f32;
-        self.steering_input.weight = ((outputs[1] - 0.5) * 2.0) as f32; // convert to value between -1.0 and 1.0
-        self.brakes_input.weight = outputs[2] as f32;
+        return inputs;
+    }
+
+    pub fn get_current_lap_time(&self) -> usize {
+        return self.lap_timer;
+    }
+
+    // the last lap ready for a leaderboard
+    pub fn get_lap_entry(&self, generation: usize) -> LapEntry {
+        let entry = LapEntry::new(
+            self.number,
+            generation,
+            self.lap_seconds,
+            self.last_lap_sectors.clone(),
+        );
+        return LapEntry {
+            lap: self.laps,
+            ..entry
+        };
+    }
+
+    pub fn get_current_lap_seconds(&self) -> f32 {
+        return self.lap_clock;
+    }
+
+    // the last ticks of the trail, fading out towards the oldest
+    pub fn draw_trail(&self, ticks: usize, colour: Color) {
+        let start = self.trail.len().saturating_sub(ticks);
+        let shown = &self.trail[start..];
+        for (i, pair) in shown.windows(2).enumerate() {
+            let alpha = 0.1 + 0.8 * i as f32 / shown.len() as f32;
+            let faded = Color {
+                a: colour.a * alpha,
+                ..colour
+            };
+            draw_line(pair[0].x, pair[0].y, pair[1].x, pair[1].y, 2.0, faded);
+        }
+    }
+
+    pub fn get_last_lap_trace(&self) -> &[TraceFrame] {
+        // the path driven on the lap that just finished
+        return &self.last_lap_trace;
+    }
+
+    pub fn get_centre(&self) -> Vec2 {
+        return self.rect.center();
+    }
+
+    pub fn get_angle(&self) -> f32 {
+        return self.angle;
+    }
+
+    pub fn get_size(&self) -> Vec2 {
+        return self.rect.size();
+    }
+
+    pub fn get_controls(&self) -> Controls {
+        // what the actuators actually did on the last tick
+        return self.controls;
+    }
 
-        self.steer = self.steering_input.weight * Car::STEER_WEIGHT;
+    fn drive(&mut self, dt: f32, model: PhysicsModel) {
+        // turn the requests into what the car can actually do
+        let forward_speed = self.velocity.dot(Vec2::from_angle(self.angle));
+        let controls = self.actuators.apply(
+            &self.accelerator_input,
+            &self.steering_input,
+            &self.brakes_input,
+            forward_speed,
+            dt,
+        );
+        self.controls = controls;
+
+        match model {
+            PhysicsModel::Arcade => self.step_arcade(&controls, dt),
+            PhysicsModel::Kinematic | PhysicsModel::Dynamic => {
+                self.step_bicycle(&controls, dt, model)
+            }
+        }
+        self.velocity = self.actuators.limit_speed(self.velocity);
+        self.position += self.velocity * dt;
+        self.distance += self.velocity.length() * dt;
+        self.update_pos(self.position.x, self.position.y);
+        self.lap_trace.push((self.rect.center(), self.angle));
+        if self.trail_on {
+            self.trail.push(self.rect.center());
+        }
+
+        // reset inputs
+        self.brakes_input.reset();
+        self.accelerator_input.reset();
+        self.steering_input.reset();
+
+        // increment time
+        self.timer += 1;
+        self.lap_timer += 1;
+        self.lap_clock += dt;
+        self.sector_clock += dt;
+    }
+
+    fn step_arcade(&mut self, controls: &Controls, dt: f32) {
+        self.steer = controls.steer * self.spec.steer_weight;
         let new_angle = self.angle + self.steer;
 
         self.angle = lerp(self.angle, new_angle, dt * 6.0);
         self.direction = Vec2::from_angle(self.angle);
 
-        self.acceleration = self.direction * (self.accelerator_input.weight * Car::MAX_ACC);
+        self.acceleration = self.direction * (controls.throttle * self.spec.max_acc());
         self.velocity += self.acceleration * dt;
 
-        let brake_friction = -self.velocity * self.brakes_input.weight * Car::BRAKING_FACTOR;
+        let brake_friction = -self.velocity * controls.brake * self.spec.braking_factor;
         self.velocity += brake_friction * dt;
 
-        let normal_fric: Vec2 = -self.velocity * FRIC_COEF;
+        let normal_fric: Vec2 = -self.velocity * self.spec.drag;
 
         let perp_direction = self.direction.perp();
         let lateral_velocity =
             self.velocity.dot(perp_direction.normalize()) * perp_direction.normalize();
-        let lateral_fric = -lateral_velocity * LAT_FRIC_COEF;
+        let lateral_fric = -lateral_velocity * self.spec.lateral_friction;
 
         // apply frictions
         self.velocity += (normal_fric) * dt;
         self.velocity += (lateral_fric); // apply lateral friction
-        self.position += self.velocity * dt;
-        self.update_pos(self.position.x, self.position.y);
+    }
 
-        // reset inputs
-        self.brakes_input.weight = 0.0;
-        self.accelerator_input.weight = 0.0;
-        self.steering_input.weight = 0.0;
+    fn step_bicycle(&mut self, controls: &Controls, dt: f32, model: PhysicsModel) {
+        let mut state = VehicleState {
+            velocity: self.velocity,
+            angle: self.angle,
+            yaw_rate: self.yaw_rate,
+            steer_angle: self.steer,
+            acceleration: self.acceleration,
+        };
 
-        // increment time
-        self.timer += 1;
-        self.lap_timer += 1;
+        let params = self.spec.bicycle_params();
+        if model == PhysicsModel::Dynamic {
+            physics::step_dynamic(&mut state, controls, &params, dt);
+        } else {
+            physics::step_kinematic(&mut state, controls, &params, dt);
+        }
+
+        self.velocity = state.velocity;
+        self.angle = state.angle;
+        self.yaw_rate = state.yaw_rate;
+        self.steer = state.steer_angle;
+        self.acceleration = state.acceleration;
+        self.direction = Vec2::from_angle(self.angle);
     }
 
     pub fn get_sector(&self, track: &Track) -> i32 {
@@ -353,94 +626,92 @@ impl Car {
                 self.steering_input.weight = 1.0;
             }
         }
-        // clamping speeds and steering
-        if (self.velocity.length() > Car::MAX_SPEED) {
-            self.velocity = ((self.velocity) / self.velocity.length()) * Car::MAX_SPEED;
-        }
     }
 
-    pub fn is_on_track(&self, track: &Track) -> bool {
-        // find the current sector
-        let sector: usize = self.get_sector(track) as usize;
-
-        // find the sector line equation
-        let points = track.get_points();
-        let p1 = points[sector];
-        let p2 = points[(sector + 1) % points.len()];
-        let coef_vec = find_line_eq(p1.x, p1.y, p2.x, p2.y);
-        let a = coef_vec.x;
-        let b = 1.0;
-        let c = coef_vec.y;
-
-        // use the formula for distance
+    pub fn get_corners(&self) -> [Vec2; 4] {
+        // the hitbox rotated to match the sprite, the long side points along the car
         let center = self.rect.center();
-        let distance = (a * center.x + b * center.y + c).abs() / (a * a + b * b).sqrt();
+        let forward = Vec2::from_angle(self.angle) * (self.rect.h / 2.0);
+        let side = forward.perp().normalize() * (self.rect.w / 2.0);
+
+        return [
+            center + forward + side,
+            center + forward - side,
+            center - forward - side,
+            center - forward + side,
+        ];
+    }
 
-        if distance > (track.get_width() / 2.0) {
-            // off the track
-            return false;
+    pub fn is_on_track(&self, track: &Track, rule: OffTrackRule) -> bool {
+        match rule {
+            OffTrackRule::Centre => track.contains_point(self.rect.center()),
+            OffTrackRule::AnyCorner => self
+                .get_corners()
+                .iter()
+                .all(|corner| track.contains_point(*corner)),
         }
-
-        return true;
     }
 
-    pub fn cast_ray(&self, track: &Track, ray_direction: Vec2) -> f32 {
-        // returns distance to line sector
-
-        let track_width = track.get_width();
-        let current_sector: usize = self.get_sector(track) as usize;
-        let points = track.get_points();
-
-        let s1 = self.rect.center();
-        let s2 = s1 + ray_direction * WINDOW_WIDTH as f32 * 5.0;
-
-        let mut shortest_interection_point: Vec2 = Vec2::MAX;
-        let mut shortest_distance: f32 = WINDOW_WIDTH as f32;
-
-        for i in 0..points.len() {
-            // finding points A, B, C, D
-            let sector_index = (current_sector + i) % points.len();
-            let sector = points[sector_index];
-            let next_sector = points[(sector_index + 1) % points.len()];
-            let prev_sector = points[(sector_index + (points.len() - 1)) % points.len()];
-            let next_next_sector = points[(sector_index + 2) % points.len()];
-
-            // finding normal for A and C
-            let direction1_left = sector - prev_sector;
-            let direction2_left = next_sector - sector;
-            let normal1_left = vec2(-direction1_left.y, direction1_left.x);
-            let normal2_left = vec2(-direction2_left.y, direction2_left.x);
-            let avg_normal_left = ((normal1_left + normal2_left) / 2.0).normalize();
-
-            // finding normal for B and D
-            let direction1_right = next_sector - sector;
-            let direction2_right = next_next_sector - next_sector;
-            let normal1_right = vec2(-direction1_right.y, direction1_right.x);
-            let normal2_right = vec2(-direction2_right.y, direction2_right.x);
-            let avg_normal_right = ((normal1_right + normal2_right) / 2.0).normalize();
-
-            // calculating points
-            let A = sector + avg_normal_left * (track_width / 2.0);
-            let B = next_sector + avg_normal_right * (track_width / 2.0);
-            let C = sector - avg_normal_left * (track_width / 2.0);
-            let D = next_sector - avg_normal_right * (track_width / 2.0);
-
-            if let Some(point1) = line_intersection(s1, s2, A, B) {
-                let distance = (point1 - s1).length();
-                if distance < shortest_distance {
-                    shortest_interection_point = point1;
-                    shortest_distance = distance;
-                }
-            } else if let Some(point2) = line_intersection(s1, s2, C, D) {
-                let distance = (point2 - s1).length();
-                if distance < shortest_distance {
-                    shortest_interection_point = point2;
-                    shortest_distance = distance;
+    pub fn apply_track_limits(&mut self, track: &Track, mode: CollisionMode, rule: OffTrackRule) {
+        match mode {
+            CollisionMode::Eliminate => {
+                if !self.is_on_track(track, rule) {
+                    self.crashed();
                 }
             }
+            CollisionMode::Walls => self.resolve_wall_collision(track, rule),
+        }
+    }
+
+    pub fn resolve_wall_collision(&mut self, track: &Track, rule: OffTrackRule) {
+        // points of the car that aren't allowed through the wall
+        let points = match rule {
+            OffTrackRule::Centre => vec![self.rect.center()],
+            OffTrackRule::AnyCorner => self.get_corners().to_vec(),
+        };
+
+        // find the deepest point through the wall
+        let mut push = Vec2::ZERO;
+        let mut wall_normal = Vec2::ZERO;
+        for point in points {
+            if track.contains_point(point) {
+                continue;
+            }
+            let (closest, normal) = track.nearest_boundary(point);
+            let depth = (closest - point).dot(normal);
+            if depth > push.length() {
+                push = normal * depth;
+                wall_normal = normal;
+            }
         }
 
-        shortest_distance
+        if wall_normal == Vec2::ZERO {
+            self.touching_wall = false;
+            return;
+        }
+
+        // move back onto the track
+        self.update_pos(self.position.x + push.x, self.position.y + push.y);
+
+        // bounce the part of the velocity going into the wall and slow the rest down
+        let into_wall = self.velocity.dot(wall_normal);
+        if into_wall < 0.0 {
+            self.velocity -= wall_normal * into_wall * (1.0 + WALL_RESTITUTION);
+        }
+        self.velocity *= WALL_SPEED_LOSS;
+
+        // only punish the first tick of each hit so sliding along a wall isn't a crash
+        if !self.touching_wall {
+            self.wall_hits += 1;
+            self.lap_check.add_wall_hit();
+            self.fitness += WALL_PUNISHMENT;
+        }
+        self.touching_wall = true;
+    }
+
+    pub fn cast_ray(&self, track: &Track, ray_direction: Vec2) -> f32 {
+        // returns distance to the closest track edge
+        track.cast_ray(self.rect.center(), ray_direction, SENSOR_RANGE)
     }
 
     pub fn cast_rays(&self, rays: usize, fov: f32, track: &Track) -> Vec<f32> {
@@ -455,8 +726,8 @@ impl Car {
             let angle = start_angle + step * ray as f32;
             let dir = Vec2::from_angle(angle.to_radians());
             let distance = self.cast_ray(track, dir);
-            // normalize the distance against the window width
-            let normalized = distance / (WINDOW_WIDTH as f32);
+            // normalize the distance against the sensor range
+            let normalized = distance / SENSOR_RANGE;
             ray_list.push(normalized);
         }
 
@@ -467,28 +738,36 @@ impl Car {
         self.crashed = true;
     }
 
-    pub fn reset(&mut self, position: Vec2) {
-        self.position = position;
+    pub fn reset(&mut self, start_pos: Vec2) {
+        // back to the start line as if the car had just been made
+        self.position = start_pos - self.rect.size() / 2.0;
         self.acceleration = Vec2::ZERO;
         self.velocity = Vec2::ZERO;
         self.angle = 0.0;
         self.steer = 0.0;
-        self.direction = Vec2::ZERO;
-        self.accelerator_input.weight = 0.0;
-        self.brakes_input.weight = 0.0;
-        self.steering_input.weight = 0.0;
+        self.yaw_rate = 0.0;
+        self.direction = Vec2::from_angle(self.angle);
+        self.accelerator_input.reset();
+        self.brakes_input.reset();
+        self.steering_input.reset();
+        self.actuators.reset();
         self.update_pos(self.position.x, self.position.y);
         self.crashed = false;
-    }
-}
-
-impl Input {
-    pub fn new_default() -> Self {
-        Self {
-            min: 0.0,
-            weight: 0.0,
-            max: 1.0,
-            default: 0.0,
-        }
+        self.touching_wall = false;
+        self.timer = 0;
+        self.lap_timer = 0;
+        self.lap_clock = 0.0;
+        self.sector_clock = 0.0;
+        self.lap_sectors.clear();
+        self.prev_checkpoint = 0;
+        self.lap_trace.clear();
+        self.trail.clear();
+        self.lap_telemetry.clear();
+        self.last_lap_telemetry.clear();
+        self.telemetry_waiting = false;
+        self.lap_check = LapCheck::new(self.rect.center());
+        self.last_lap_invalid = None;
+        self.best_lap_seconds = None;
+        self.distance = 0.0;
     }
 }
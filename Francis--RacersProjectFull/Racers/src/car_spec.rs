@@ -0,0 +1,377 @@
+use crate::actuator::ActuatorLimits;
+use crate::physics::BicycleParams;
+use macroquad::prelude::*;
+use std::f32::consts::PI;
+use std::fs;
+
+pub const CAR_CONFIG_PATH: &str = "cars.cfg";
+
+// everything that makes one class of car different from another
+// lengths are in pixels, speeds in pixels per second
+#[derive(Clone)]
+pub struct CarSpec {
+    pub name: String,
+    pub sprite: String, // path to a png, empty uses the built in car
+    pub tint: Color,
+
+    // hitbox and sprite size
+    pub width: f32,
+    pub length: f32,
+
+    // engine and friction
+    pub mass: f32,
+    pub engine_force: f32, // acceleration is this divided by the mass
+    pub braking_factor: f32,
+    pub drag: f32,
+    pub lateral_friction: f32, // arcade model sideways grip per tick
+
+    // steering and tyres
+    pub steer_weight: f32, // arcade model steering per tick
+    pub wheelbase: f32,
+    pub max_steer_angle: f32,
+    pub steer_falloff_speed: f32,
+    pub grip: f32,
+    pub peak_slip_angle: f32,
+
+    pub limits: ActuatorLimits,
+}
+
+// a spec paired with its loaded sprite so every car of the class can share the texture
+#[derive(Clone)]
+pub struct CarClass {
+    pub spec: CarSpec,
+    pub texture: Option<Texture2D>, // None without a window, the cars are never drawn then
+}
+
+impl CarSpec {
+    pub fn kart() -> Self {
+        Self {
+            name: "kart".to_string(),
+            sprite: String::new(),
+            tint: color_u8!(140, 220, 255, 255),
+            width: 24.0,
+            length: 44.0,
+            mass: 25.0,
+            engine_force: 10000.0,
+            braking_factor: 1.2,
+            drag: 0.88,
+            lateral_friction: 0.08,
+            steer_weight: PI / 5.0,
+            wheelbase: 28.0,
+            max_steer_angle: (40.0 / 180.0) * PI,
+            steer_falloff_speed: 300.0,
+            grip: 1000.0,
+            peak_slip_angle: 0.12,
+            limits: ActuatorLimits {
+                steer_slew: 5.0,
+                throttle_ramp: 4.0,
+                brake_ramp: 8.0,
+                max_speed: 300.0,
+                max_reverse_speed: 80.0,
+            },
+        }
+    }
+
+    // the original car
+    pub fn gt() -> Self {
+        Self {
+            name: "GT".to_string(),
+            sprite: String::new(),
+            tint: WHITE,
+            width: 30.0,
+            length: 60.0,
+            mass: 40.0,
+            engine_force: 16000.0,
+            braking_factor: 0.9,
+            drag: 0.88,
+            lateral_friction: 0.05,
+            steer_weight: PI / 6.0,
+            wheelbase: 40.0,
+            max_steer_angle: (40.0 / 180.0) * PI,
+            steer_falloff_speed: 250.0,
+            grip: 900.0,
+            peak_slip_angle: 0.15,
+            limits: ActuatorLimits {
+                steer_slew: 4.0,
+                throttle_ramp: 3.0,
+                brake_ramp: 6.0,
+                max_speed: 350.0,
+                max_reverse_speed: 100.0,
+            },
+        }
+    }
+
+    pub fn truck() -> Self {
+        Self {
+            name: "truck".to_string(),
+            sprite: String::new(),
+            tint: color_u8!(255, 170, 120, 255),
+            width: 40.0,
+            length: 80.0,
+            mass: 120.0,
+            engine_force: 30000.0,
+            braking_factor: 0.6,
+            drag: 0.7,
+            lateral_friction: 0.03,
+            steer_weight: PI / 8.0,
+            wheelbase: 56.0,
+            max_steer_angle: (35.0 / 180.0) * PI,
+            steer_falloff_speed: 200.0,
+            grip: 600.0,
+            peak_slip_angle: 0.18,
+            limits: ActuatorLimits {
+                steer_slew: 2.5,
+                throttle_ramp: 1.5,
+                brake_ramp: 3.0,
+                max_speed: 250.0,
+                max_reverse_speed: 60.0,
+            },
+        }
+    }
+
+    pub fn presets() -> Vec<Self> {
+        return vec![Self::kart(), Self::gt(), Self::truck()];
+    }
+
+    pub fn max_acc(&self) -> f32 {
+        return self.engine_force / self.mass;
+    }
+
+    pub fn bicycle_params(&self) -> BicycleParams {
+        BicycleParams {
+            wheelbase: self.wheelbase,
+            front_axle: self.wheelbase / 2.0,
+            max_steer_angle: self.max_steer_angle,
+            steer_falloff_speed: self.steer_falloff_speed,
+            mass: self.mass,
+            drive_force: self.engine_force,
+            braking_factor: self.braking_factor,
+            drag: self.drag,
+            grip: self.grip,
+            peak_slip_angle: self.peak_slip_angle,
+        }
+    }
+
+    pub fn load_all(path: &str) -> Vec<Self> {
+        // the presets are always available, the config file can change them or add more
+        let mut specs = Self::presets();
+        match fs::read_to_string(path) {
+            Ok(text) => Self::parse_into(&text, &mut specs),
+            Err(_) => println!("[car_spec.rs] - No {} found, using the built in cars", path),
+        }
+        return specs;
+    }
+
+    // config format:
+    // [name]          starts a class, copying the preset of the same name if there is one
+    // key = value     sets a field, angles are in degrees and the tint is r,g,b
+    pub fn parse_into(text: &str, specs: &mut Vec<Self>) {
+        let mut current: Option<usize> = None;
+
+        for (line_number, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            if line.starts_with('[') && line.ends_with(']') {
+                let name = line[1..line.len() - 1].trim();
+                let index = match specs.iter().position(|s| s.name.eq_ignore_ascii_case(name)) {
+                    Some(index) => index,
+                    None => {
+                        // new classes start from the GT
+                        let mut spec = Self::gt();
+                        spec.name = name.to_string();
+                        specs.push(spec);
+                        specs.len() - 1
+                    }
+                };
+                current = Some(index);
+                continue;
+            }
+
+            let (Some(index), Some((key, value))) = (current, line.split_once('=')) else {
+                println!(
+                    "[car_spec.rs] - Skipping line {}: {}",
+                    line_number + 1,
+                    line
+                );
+                continue;
+            };
+            if !specs[index].set(key.trim(), value.trim()) {
+                println!(
+                    "[car_spec.rs] - Bad setting on line {}: {}",
+                    line_number + 1,
+                    line
+                );
+            }
+        }
+    }
+
+    fn set(&mut self, key: &str, value: &str) -> bool {
+        if key == "sprite" {
+            self.sprite = value.to_string();
+            return true;
+        }
+        if key == "tint" {
+            let parts: Vec<Option<u8>> = value.split(',').map(|p| p.trim().parse().ok()).collect();
+            if let [Some(r), Some(g), Some(b)] = parts[..] {
+                self.tint = Color::from_rgba(r, g, b, 255);
+                return true;
+            }
+            return false;
+        }
+
+        let Ok(number) = value.parse::<f32>() else {
+            return false;
+        };
+        if !number.is_finite() {
+            return false;
+        }
+        // sizes and things the physics divides by, 0 or less gives nan positions
+        let positive = [
+            "width",
+            "length",
+            "mass",
+            "wheelbase",
+            "peak_slip_angle",
+            "steer_falloff_speed",
+            "max_speed",
+        ];
+        if positive.contains(&key) && number <= 0.0 {
+            return false;
+        }
+        match key {
+            "width" => self.width = number,
+            "length" => self.length = number,
+            "mass" => self.mass = number,
+            "engine_force" => self.engine_force = number,
+            "braking_factor" => self.braking_factor = number,
+            "drag" => self.drag = number,
+            "lateral_friction" => self.lateral_friction = number,
+            "steer_weight" => self.steer_weight = number.to_radians(),
+            "wheelbase" => self.wheelbase = number,
+            "max_steer_angle" => self.max_steer_angle = number.to_radians(),
+            "steer_falloff_speed" => self.steer_falloff_speed = number,
+            "grip" => self.grip = number,
+            "peak_slip_angle" => self.peak_slip_angle = number.to_radians(),
+            "steer_slew" => self.limits.steer_slew = number,
+            "throttle_ramp" => self.limits.throttle_ramp = number,
+            "brake_ramp" => self.limits.brake_ramp = number,
+            "max_speed" => self.limits.max_speed = number,
+            "max_reverse_speed" => self.limits.max_reverse_speed = number,
+            _ => return false,
+        }
+        return true;
+    }
+
+    pub fn load_class(&self) -> CarClass {
+        // fall back to the built in sprite if the file can't be read or decoded
+        let image = fs::read(&self.sprite)
+            .ok()
+            .and_then(|bytes| Image::from_file_with_format(&bytes, None).ok());
+        let texture = match image {
+            Some(image) => Texture2D::from_image(&image),
+            None => {
+                if !self.sprite.is_empty() {
+                    println!("[car_spec.rs] - Couldn't load sprite {}", self.sprite);
+                }
+                Texture2D::from_file_with_format(include_bytes!("../assets/car.png"), None)
+            }
+        };
+        CarClass {
+            spec: self.clone(),
+            texture: Some(texture),
+        }
+    }
+
+    // for training without a window, textures can't be made without one
+    pub fn headless_class(&self) -> CarClass {
+        CarClass {
+            spec: self.clone(),
+            texture: None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(text: &str) -> Vec<CarSpec> {
+        let mut specs = CarSpec::presets();
+        CarSpec::parse_into(text, &mut specs);
+        return specs;
+    }
+
+    fn find<'a>(specs: &'a [CarSpec], name: &str) -> &'a CarSpec {
+        return specs.iter().find(|s| s.name == name).unwrap();
+    }
+
+    #[test]
+    fn settings_change_the_preset_of_the_same_name() {
+        let specs = parse("[gt]\nmass = 55\nmax_speed = 400\n");
+        assert_eq!(specs.len(), 3);
+        let gt = find(&specs, "GT");
+        assert_eq!(gt.mass, 55.0);
+        assert_eq!(gt.limits.max_speed, 400.0);
+        assert_eq!(gt.width, CarSpec::gt().width);
+    }
+
+    #[test]
+    fn new_classes_start_from_the_gt() {
+        let specs = parse("[rally]\nwidth = 28\n");
+        assert_eq!(specs.len(), 4);
+        let rally = find(&specs, "rally");
+        assert_eq!(rally.width, 28.0);
+        assert_eq!(rally.length, CarSpec::gt().length);
+    }
+
+    #[test]
+    fn angles_are_in_degrees_and_the_tint_is_rgb() {
+        let specs = parse("[kart]\nmax_steer_angle = 90\ntint = 255, 0, 128\nsprite = a.png\n");
+        let kart = find(&specs, "kart");
+        assert!((kart.max_steer_angle - PI / 2.0).abs() < 1e-6);
+        assert_eq!(kart.tint, Color::from_rgba(255, 0, 128, 255));
+        assert_eq!(kart.sprite, "a.png");
+    }
+
+    #[test]
+    fn comments_blank_lines_and_lines_outside_a_class_are_skipped() {
+        let specs = parse("mass = 1\n\n# [truck]\n  [truck]  \n  # mass = 2\n drag = 0.5 \n");
+        let truck = find(&specs, "truck");
+        assert_eq!(truck.mass, CarSpec::truck().mass);
+        assert_eq!(truck.drag, 0.5);
+    }
+
+    #[test]
+    fn bad_values_leave_the_setting_alone() {
+        let text = "[gt]\nmass = heavy\nmass = 0\nwidth = -3\nlength = inf\ndrag = NaN\n\
+                    wheelbase = 0\npeak_slip_angle = -1\nsteer_falloff_speed = 0\n\
+                    max_speed = -10\ntint = 1,2\ncolour = 3\n";
+        let specs = parse(text);
+        let gt = find(&specs, "GT");
+        let original = CarSpec::gt();
+        assert_eq!(gt.mass, original.mass);
+        assert_eq!(gt.width, original.width);
+        assert_eq!(gt.length, original.length);
+        assert_eq!(gt.drag, original.drag);
+        assert_eq!(gt.wheelbase, original.wheelbase);
+        assert_eq!(gt.peak_slip_angle, original.peak_slip_angle);
+        assert_eq!(gt.steer_falloff_speed, original.steer_falloff_speed);
+        assert_eq!(gt.limits.max_speed, original.limits.max_speed);
+        assert_eq!(gt.tint, original.tint);
+    }
+
+    #[test]
+    fn set_reports_bad_settings() {
+        let mut spec = CarSpec::gt();
+        assert!(spec.set("mass", "60"));
+        assert!(spec.set("drag", "0"));
+        assert!(spec.set("max_reverse_speed", "0"));
+        assert!(!spec.set("mass", "0"));
+        assert!(!spec.set("mass", "-inf"));
+        assert!(!spec.set("grip", "NaN"));
+        assert!(!spec.set("unknown", "1"));
+    }
+}
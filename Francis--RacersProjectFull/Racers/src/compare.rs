@@ -0,0 +1,428 @@
+use crate::graph::draw_chart;
+use crate::stats::*;
+use crate::ui::Button;
+use macroquad::prelude::*;
+use std::fs;
+
+// the fitness files written before runs got their own folders
+const LEGACY_PREFIX: &str = "fitness_values";
+pub const COMPARISON_PATH: &str = "runs/comparison.csv";
+const COMPARISON_HEADER: &str =
+    "run,generations,final_best_fitness,peak_fitness,first_lap_generation,final_best_lap,best_lap,settings";
+
+const ROWS_SHOWN: usize = 12;
+const ROW_HEIGHT: f32 = 28.0;
+const TABLE_TOP: f32 = 150.0;
+const COLUMNS: [f32; 8] = [100.0, 130.0, 440.0, 520.0, 640.0, 750.0, 850.0, 960.0];
+const HEADINGS: [&str; 8] = [
+    "",
+    "Run",
+    "Gens",
+    "Final best",
+    "Peak",
+    "1st lap",
+    "Final lap",
+    "Best lap",
+];
+const RUN_COLOURS: [Color; 8] = [
+    color_u8!(220, 40, 40, 255),
+    color_u8!(30, 90, 220, 255),
+    color_u8!(0, 160, 0, 255),
+    color_u8!(230, 140, 0, 255),
+    color_u8!(150, 50, 200, 255),
+    color_u8!(0, 170, 170, 255),
+    color_u8!(120, 80, 30, 255),
+    color_u8!(90, 90, 90, 255),
+];
+
+// one run's stats and what it was set up with
+pub struct RunStats {
+    pub name: String,
+    pub settings: Vec<(String, String)>, // from the manifest, empty for old files
+    pub history: Vec<GenerationStats>,
+}
+
+// main menu screen that overlays the learning curves of several runs
+// click a row to show or hide its curves
+pub struct RunComparison {
+    runs: Vec<RunStats>,
+    shown: Vec<bool>,
+    export_button: Button,
+    message: String,
+}
+
+impl Default for RunComparison {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl RunStats {
+    pub fn final_best_fitness(&self) -> Option<i32> {
+        return self.history.last().map(|s| s.best);
+    }
+
+    pub fn peak_fitness(&self) -> Option<i32> {
+        return self.history.iter().map(|s| s.best).max();
+    }
+
+    // the first generation where any car finished a lap
+    pub fn first_lap_generation(&self) -> Option<usize> {
+        return self
+            .history
+            .iter()
+            .find(|s| s.lapped > 0 || s.best_lap.is_some())
+            .map(|s| s.generation);
+    }
+
+    pub fn final_best_lap(&self) -> Option<f32> {
+        return self.history.last().and_then(|s| s.best_lap);
+    }
+
+    pub fn best_lap(&self) -> Option<f32> {
+        return self
+            .history
+            .iter()
+            .filter_map(|s| s.best_lap)
+            .min_by(|a, b| a.total_cmp(b));
+    }
+
+    fn to_line(&self) -> String {
+        // settings are joined with ; so they stay in one column
+        let settings: Vec<String> = self
+            .settings
+            .iter()
+            .map(|(key, value)| format!("{}={}", key, value))
+            .collect();
+        return format!(
+            "{},{},{},{},{},{},{},{}",
+            self.name,
+            self.history.len(),
+            optional(self.final_best_fitness()),
+            optional(self.peak_fitness()),
+            optional(self.first_lap_generation()),
+            optional(self.final_best_lap().map(|t| format!("{:.3}", t))),
+            optional(self.best_lap().map(|t| format!("{:.3}", t))),
+            settings.join(";").replace(',', " ")
+        );
+    }
+}
+
+fn optional<T: ToString>(value: Option<T>) -> String {
+    return value.map(|v| v.to_string()).unwrap_or_default();
+}
+
+// every run folder, oldest first, then the old fitness files
+pub fn load_runs() -> Vec<RunStats> {
+    let mut runs = vec![];
+
+    let mut dirs: Vec<_> = fs::read_dir(RUNS_DIR)
+        .into_iter()
+        .flatten()
+        .flatten()
+        .map(|e| e.path())
+        .filter(|p| p.is_dir())
+        .collect();
+    // the folder names start with the date so this puts them in order
+    dirs.sort();
+    for dir in dirs {
+        let stats_path = dir.join("generations.csv");
+        let Some(history) = load_history(&stats_path.to_string_lossy()) else {
+            continue;
+        };
+        let settings = fs::read_to_string(dir.join("manifest.txt"))
+            .unwrap_or_default()
+            .lines()
+            .filter_map(|line| line.split_once(" = "))
+            .map(|(key, value)| (key.to_string(), value.to_string()))
+            .collect();
+        runs.push(RunStats {
+            name: dir
+                .file_name()
+                .map(|n| n.to_string_lossy().to_string())
+                .unwrap_or_default(),
+            settings,
+            history,
+        });
+    }
+
+    let mut legacy: Vec<_> = fs::read_dir(".")
+        .into_iter()
+        .flatten()
+        .flatten()
+        .map(|e| e.file_name().to_string_lossy().to_string())
+        .filter(|name| name.starts_with(LEGACY_PREFIX) && name.ends_with(".csv"))
+        .collect();
+    legacy.sort();
+    for name in legacy {
+        if let Some(history) = load_history(&name) {
+            runs.push(RunStats {
+                name,
+                settings: vec![],
+                history,
+            });
+        }
+    }
+    return runs;
+}
+
+// writes the summary of each run to one csv, returns false if it couldn't
+pub fn export_comparison(runs: &[&RunStats], path: &str) -> bool {
+    let mut text = format!("{}\n", COMPARISON_HEADER);
+    for run in runs.iter() {
+        text += &format!("{}\n", run.to_line());
+    }
+    let result = fs::create_dir_all(RUNS_DIR).and_then(|_| fs::write(path, text));
+    if let Err(e) = result {
+        println!("[compare.rs] - Couldn't write {}: {}", path, e);
+        return false;
+    }
+    return true;
+}
+
+impl RunComparison {
+    pub fn new() -> Self {
+        let mut runs = load_runs();
+        // only the newest runs fit on the screen
+        if runs.len() > ROWS_SHOWN {
+            runs.drain(..runs.len() - ROWS_SHOWN);
+        }
+        // start with the newest few shown
+        let shown = (0..runs.len()).map(|i| i + 3 >= runs.len()).collect();
+
+        Self {
+            runs,
+            shown,
+            export_button: Button::new(
+                100.0,
+                720.0,
+                300.0,
+                60.0,
+                "Export table".to_string(),
+                LIGHTGRAY,
+            ),
+            message: String::new(),
+        }
+    }
+
+    pub fn update(&mut self) {
+        // click a row to show or hide that run
+        if is_mouse_button_pressed(MouseButton::Left) {
+            let (x, y) = mouse_position();
+            let row = ((y - TABLE_TOP) / ROW_HEIGHT).floor();
+            if (COLUMNS[0]..1100.0).contains(&x) && row >= 0.0 && (row as usize) < self.runs.len() {
+                let i = row as usize;
+                self.shown[i] = !self.shown[i];
+            }
+        }
+
+        if self.export_button.check_pressed() {
+            self.export_button.reset();
+            let runs: Vec<&RunStats> = self
+                .runs
+                .iter()
+                .zip(self.shown.iter())
+                .filter(|(_, shown)| **shown)
+                .map(|(run, _)| run)
+                .collect();
+            self.message = match runs.is_empty() {
+                true => "Select some runs first".to_string(),
+                false => match export_comparison(&runs, COMPARISON_PATH) {
+                    true => format!("Saved {} runs to {}", runs.len(), COMPARISON_PATH),
+                    false => "Couldn't save the table".to_string(),
+                },
+            };
+        }
+    }
+
+    pub fn draw(&self) {
+        draw_text("Compare Runs", 100.0, 80.0, 60.0, BLACK);
+        draw_text(
+            "Click a run to show or hide it",
+            100.0,
+            110.0,
+            22.0,
+            DARKGRAY,
+        );
+
+        for (column, heading) in COLUMNS.iter().zip(HEADINGS.iter()) {
+            draw_text(heading, *column, TABLE_TOP - 10.0, 25.0, BLACK);
+        }
+        if self.runs.is_empty() {
+            draw_text("No runs saved yet", 100.0, TABLE_TOP + 25.0, 25.0, DARKGRAY);
+        }
+
+        for (i, run) in self.runs.iter().enumerate() {
+            let y = TABLE_TOP + ROW_HEIGHT * i as f32;
+            let colour = RUN_COLOURS[i % RUN_COLOURS.len()];
+            match self.shown[i] {
+                true => draw_rectangle(COLUMNS[0], y + 6.0, 16.0, 16.0, colour),
+                false => draw_rectangle_lines(COLUMNS[0], y + 6.0, 16.0, 16.0, 2.0, colour),
+            }
+            let row = [
+                String::new(),
+                run.name.clone(),
+                format!("{}", run.history.len()),
+                optional(run.final_best_fitness()),
+                optional(run.peak_fitness()),
+                optional(run.first_lap_generation()),
+                optional(run.final_best_lap().map(|t| format!("{:.3}s", t))),
+                optional(run.best_lap().map(|t| format!("{:.3}s", t))),
+            ];
+            for (column, text) in COLUMNS.iter().zip(row.iter()) {
+                draw_text(text, *column, y + ROW_HEIGHT * 0.75, 22.0, BLACK);
+            }
+        }
+
+        self.draw_curves();
+
+        self.export_button.draw();
+        draw_text(&self.message, 420.0, 757.0, 25.0, BLACK);
+    }
+
+    fn draw_curves(&self) {
+        let mut best = vec![];
+        let mut laps = vec![];
+        for (i, run) in self.runs.iter().enumerate() {
+            if !self.shown[i] {
+                continue;
+            }
+            let colour = RUN_COLOURS[i % RUN_COLOURS.len()];
+            let fitness: Vec<Option<f32>> =
+                run.history.iter().map(|s| Some(s.best as f32)).collect();
+            let lap_times: Vec<Option<f32>> = run.history.iter().map(|s| s.best_lap).collect();
+            best.push((fitness, colour));
+            laps.push((lap_times, colour));
+        }
+
+        // the table's colours stand in for a legend
+        let best_series: Vec<(&str, &[Option<f32>], Color)> =
+            best.iter().map(|(v, c)| ("", v.as_slice(), *c)).collect();
+        let lap_series: Vec<(&str, &[Option<f32>], Color)> =
+            laps.iter().map(|(v, c)| ("", v.as_slice(), *c)).collect();
+        draw_chart(
+            Rect::new(145.0, 530.0, 400.0, 150.0),
+            "Best fitness",
+            &best_series,
+            false,
+        );
+        draw_chart(
+            Rect::new(650.0, 530.0, 350.0, 150.0),
+            "Best lap (s)",
+            &lap_series,
+            false,
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // a generation as it's written to generations.csv
+    fn generation(generation: usize, best: i32, best_lap: Option<f32>) -> GenerationStats {
+        let lapped = best_lap.map_or(0, |_| 1);
+        let lap = best_lap.map_or(String::new(), |t| format!("{:.3}", t));
+        let row = format!(
+            "{},{},10.0,10.0,0,1.0,5,{},{},100.0,0.1",
+            generation, best, lapped, lap
+        );
+        return GenerationStats::from_csv_row(&row).unwrap();
+    }
+
+    fn run(history: Vec<GenerationStats>, settings: &[(&str, &str)]) -> RunStats {
+        return RunStats {
+            name: "2024-01-01_12-00-00_7".to_string(),
+            settings: settings
+                .iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect(),
+            history,
+        };
+    }
+
+    // a file of its own in the temp folder, removed again by the test
+    fn temp_file(name: &str, text: &str) -> String {
+        let path = std::env::temp_dir().join(format!("racers_{}_{}", std::process::id(), name));
+        fs::write(&path, text).unwrap();
+        return path.to_string_lossy().to_string();
+    }
+
+    #[test]
+    fn summary_of_a_run_that_lapped() {
+        let run = run(
+            vec![
+                generation(0, 100, None),
+                generation(1, 400, None),
+                generation(2, 900, Some(45.5)),
+                generation(3, 850, Some(41.25)),
+                generation(4, 700, Some(43.0)),
+            ],
+            &[],
+        );
+        assert_eq!(run.final_best_fitness(), Some(700));
+        assert_eq!(run.peak_fitness(), Some(900));
+        assert_eq!(run.first_lap_generation(), Some(2));
+        assert_eq!(run.final_best_lap(), Some(43.0));
+        assert_eq!(run.best_lap(), Some(41.25));
+        assert_eq!(
+            run.to_line(),
+            "2024-01-01_12-00-00_7,5,700,900,2,43.000,41.250,"
+        );
+    }
+
+    #[test]
+    fn run_with_no_laps_leaves_the_lap_columns_empty() {
+        let run = run(vec![generation(0, 50, None), generation(1, 80, None)], &[]);
+        assert_eq!(run.first_lap_generation(), None);
+        assert_eq!(run.final_best_lap(), None);
+        assert_eq!(run.best_lap(), None);
+        assert_eq!(run.to_line(), "2024-01-01_12-00-00_7,2,80,80,,,,");
+    }
+
+    #[test]
+    fn empty_run_has_no_summary() {
+        let run = run(vec![], &[]);
+        assert_eq!(run.final_best_fitness(), None);
+        assert_eq!(run.peak_fitness(), None);
+        assert_eq!(run.to_line(), "2024-01-01_12-00-00_7,0,,,,,,");
+    }
+
+    #[test]
+    fn settings_with_commas_stay_in_one_column() {
+        let run = run(
+            vec![generation(0, 10, None)],
+            &[("car_classes", "GT,kart"), ("seed", "7")],
+        );
+        let line = run.to_line();
+        assert_eq!(
+            line.split(',').count(),
+            COMPARISON_HEADER.split(',').count()
+        );
+        assert!(line.ends_with(",car_classes=GT kart;seed=7"));
+    }
+
+    #[test]
+    fn legacy_two_column_files_load() {
+        let path = temp_file("legacy.csv", "generation,best\n0,120\n1,340\n\n2,310\n");
+        let history = load_history(&path);
+        fs::remove_file(&path).unwrap();
+
+        let run = run(history.unwrap(), &[]);
+        assert_eq!(run.history.len(), 3);
+        assert_eq!(run.final_best_fitness(), Some(310));
+        assert_eq!(run.peak_fitness(), Some(340));
+        assert_eq!(run.first_lap_generation(), None);
+        assert_eq!(run.to_line(), "2024-01-01_12-00-00_7,3,310,340,,,,");
+    }
+
+    #[test]
+    fn bad_rows_are_skipped_and_missing_files_are_none() {
+        let path = temp_file("bad_rows.csv", "generation,best\n0,120\nnot a row\n1,150\n");
+        let history = load_history(&path);
+        fs::remove_file(&path).unwrap();
+        assert_eq!(history.unwrap().len(), 2);
+
+        assert!(load_history("no/such/file.csv").is_none());
+    }
+}
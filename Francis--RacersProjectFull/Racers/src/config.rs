@@ -0,0 +1,104 @@
+use crate::car::{CollisionMode, OffTrackRule};
+use crate::car_spec::CarSpec;
+use crate::physics::PhysicsModel;
+use crate::telemetry::TelemetryMode;
+
+// settings chosen on the main menu for a new simulation
+#[derive(Clone)]
+pub struct SimConfig {
+    pub population_size: usize,
+    pub time_limit: u32, // ticks per generation
+    pub off_track_rule: OffTrackRule,
+    pub collision_mode: CollisionMode,
+    pub physics_model: PhysicsModel,
+    pub car_classes: Vec<CarSpec>, // more than one gives a mixed race
+    pub seed: u64,                 // seeds the random numbers when the population is made
+    pub record_replays: bool,
+    pub log_laps: bool, // print laps to the terminal as well as the leaderboard
+    pub telemetry: TelemetryMode,
+    pub reinject_elites: bool, // the elites race again in every generation
+    pub track_id: String,
+    pub mutation_scale: f32, // multiplies every mutation rate
+    pub parent_pool: usize,  // children are bred from two of this many of the best cars
+
+    // island model, see Archipelago
+    pub islands: usize, // populations trained side by side, 1 for a single population
+    pub migration_interval: usize, // generations between migrations
+    pub migrants: usize, // genomes sent to the next island each migration
+
+    pub headless: bool, // no window, so the cars are made without their sprites
+}
+
+impl Default for SimConfig {
+    fn default() -> Self {
+        Self {
+            population_size: 220,
+            time_limit: 1250,
+            off_track_rule: OffTrackRule::AnyCorner,
+            collision_mode: CollisionMode::Eliminate,
+            physics_model: PhysicsModel::Arcade,
+            car_classes: vec![CarSpec::gt()],
+            seed: 0,
+            record_replays: false,
+            log_laps: false,
+            telemetry: TelemetryMode::Off,
+            reinject_elites: false,
+            track_id: "test_track1".to_string(),
+            mutation_scale: 1.0,
+            parent_pool: 2,
+            islands: 1,
+            migration_interval: 5,
+            migrants: 2,
+            headless: false,
+        }
+    }
+}
+
+impl SimConfig {
+    // the settings as key value pairs, for files that record how a run was set up
+    pub fn describe(&self) -> Vec<(String, String)> {
+        let classes: Vec<String> = self.car_classes.iter().map(|c| c.name.clone()).collect();
+        return vec![
+            (
+                "population_size".to_string(),
+                self.population_size.to_string(),
+            ),
+            ("time_limit".to_string(), self.time_limit.to_string()),
+            (
+                "off_track_rule".to_string(),
+                format!("{:?}", self.off_track_rule),
+            ),
+            (
+                "collision_mode".to_string(),
+                format!("{:?}", self.collision_mode),
+            ),
+            (
+                "physics_model".to_string(),
+                format!("{:?}", self.physics_model),
+            ),
+            ("car_classes".to_string(), classes.join(",")),
+            ("seed".to_string(), self.seed.to_string()),
+            (
+                "record_replays".to_string(),
+                self.record_replays.to_string(),
+            ),
+            ("telemetry".to_string(), format!("{:?}", self.telemetry)),
+            (
+                "reinject_elites".to_string(),
+                self.reinject_elites.to_string(),
+            ),
+            (
+                "mutation_scale".to_string(),
+                self.mutation_scale.to_string(),
+            ),
+            ("parent_pool".to_string(), self.parent_pool.to_string()),
+            ("islands".to_string(), self.islands.to_string()),
+            (
+                "migration_interval".to_string(),
+                self.migration_interval.to_string(),
+            ),
+            ("migrants".to_string(), self.migrants.to_string()),
+            ("headless".to_string(), self.headless.to_string()),
+        ];
+    }
+}
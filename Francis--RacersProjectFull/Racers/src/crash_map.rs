@@ -0,0 +1,284 @@
+use crate::track::Track;
+use macroquad::prelude::*;
+use std::collections::HashMap;
+use std::fs;
+use std::fs::OpenOptions;
+use std::io::prelude::*;
+
+// world units per heatmap square
+const CELL_SIZE: f32 = 25.0;
+const CRASHES_NAME: &str = "crashes.csv";
+const SECTORS_NAME: &str = "crash_sectors.csv";
+const CRASHES_HEADER: &str = "generation,car,kind,x,y,sector";
+const SECTORS_HEADER: &str = "sector,crashes,wall_hits,share,this_generation";
+
+// a car leaving the track for good or, with solid edges, touching a wall
+pub struct CrashEvent {
+    pub generation: usize,
+    pub car_number: usize,
+    pub position: Vec2,
+    pub sector: usize,
+    pub wall_hit: bool,
+}
+
+#[derive(Clone, Default)]
+struct SectorCrashes {
+    crashes: u32,
+    wall_hits: u32,
+    this_generation: u32, // both kinds, in the generation being run
+}
+
+// where the population comes off the track, over every generation of a run
+// H shows the heatmap over the track
+pub struct CrashMap {
+    pub visible: bool,
+    cells: HashMap<(i32, i32), u32>,
+    busiest: u32, // most events in one cell, the hottest colour
+    sectors: Vec<SectorCrashes>,
+    unsaved: Vec<CrashEvent>,
+}
+
+impl CrashMap {
+    pub fn new(sector_count: usize) -> Self {
+        Self {
+            visible: false,
+            cells: HashMap::new(),
+            busiest: 0,
+            sectors: vec![SectorCrashes::default(); sector_count],
+            unsaved: vec![],
+        }
+    }
+
+    pub fn add(&mut self, event: CrashEvent) {
+        let cell = (
+            (event.position.x / CELL_SIZE).floor() as i32,
+            (event.position.y / CELL_SIZE).floor() as i32,
+        );
+        let count = self.cells.entry(cell).or_insert(0);
+        *count += 1;
+        self.busiest = self.busiest.max(*count);
+
+        if let Some(sector) = self.sectors.get_mut(event.sector) {
+            match event.wall_hit {
+                true => sector.wall_hits += 1,
+                false => sector.crashes += 1,
+            }
+            sector.this_generation += 1;
+        }
+        self.unsaved.push(event);
+    }
+
+    pub fn total(&self) -> u32 {
+        return self.sectors.iter().map(|s| s.crashes + s.wall_hits).sum();
+    }
+
+    // the sector with the most events, None before anything has crashed
+    pub fn worst_sector(&self) -> Option<(usize, u32)> {
+        return self
+            .sectors
+            .iter()
+            .map(|s| s.crashes + s.wall_hits)
+            .enumerate()
+            .filter(|(_, count)| *count > 0)
+            .max_by_key(|(_, count)| *count);
+    }
+
+    pub fn update(&mut self) {
+        if is_key_pressed(KeyCode::H) {
+            self.visible = !self.visible;
+        }
+    }
+
+    // drawn through the camera, on top of the track
+    pub fn draw(&self, track: &Track) {
+        if !self.visible || self.busiest == 0 {
+            return;
+        }
+        for ((x, y), count) in self.cells.iter() {
+            // yellow for the odd crash up to red for the worst spot
+            let heat = *count as f32 / self.busiest as f32;
+            let colour = Color::new(1.0, 1.0 - heat, 0.0, 0.3 + heat * 0.5);
+            draw_rectangle(
+                *x as f32 * CELL_SIZE,
+                *y as f32 * CELL_SIZE,
+                CELL_SIZE,
+                CELL_SIZE,
+                colour,
+            );
+        }
+
+        // the count for each sector next to its middle
+        let points = track.get_points();
+        for (i, sector) in self.sectors.iter().enumerate() {
+            let count = sector.crashes + sector.wall_hits;
+            if count == 0 {
+                continue;
+            }
+            let middle = (points[i] + points[(i + 1) % points.len()]) / 2.0;
+            draw_text(&count.to_string(), middle.x, middle.y, 30.0, BLACK);
+        }
+    }
+
+    // adds the generation's crashes to the run's log and rewrites the sector table
+    pub fn save_generation(&mut self, run_dir: &str) {
+        let path = format!("{}/{}", run_dir, CRASHES_NAME);
+        let result = fs::exists(&path).and_then(|exists| {
+            let mut file = OpenOptions::new().create(true).append(true).open(&path)?;
+            if !exists {
+                writeln!(file, "{}", CRASHES_HEADER)?;
+            }
+            for event in self.unsaved.iter() {
+                let kind = match event.wall_hit {
+                    true => "wall",
+                    false => "crash",
+                };
+                writeln!(
+                    file,
+                    "{},{},{},{:.1},{:.1},{}",
+                    event.generation,
+                    event.car_number,
+                    kind,
+                    event.position.x,
+                    event.position.y,
+                    event.sector
+                )?;
+            }
+            Ok(())
+        });
+        if let Err(e) = result {
+            println!("[crash_map.rs] - Couldn't save crashes to {}: {}", path, e);
+        }
+
+        let path = format!("{}/{}", run_dir, SECTORS_NAME);
+        if let Err(e) = fs::write(&path, self.sector_table()) {
+            println!("[crash_map.rs] - Couldn't save {}: {}", path, e);
+        }
+    }
+
+    // called after every generation, saved or not, so runs without a log don't keep
+    // every event forever
+    pub fn end_generation(&mut self) {
+        self.unsaved.clear();
+        for sector in self.sectors.iter_mut() {
+            sector.this_generation = 0;
+        }
+    }
+
+    fn sector_table(&self) -> String {
+        let total = self.total().max(1) as f32;
+        let mut text = format!("{}\n", SECTORS_HEADER);
+        for (i, sector) in self.sectors.iter().enumerate() {
+            let share = (sector.crashes + sector.wall_hits) as f32 / total;
+            text += &format!(
+                "{},{},{},{:.3},{}\n",
+                i, sector.crashes, sector.wall_hits, share, sector.this_generation
+            );
+        }
+        return text;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn event(sector: usize, wall_hit: bool, position: Vec2) -> CrashEvent {
+        return CrashEvent {
+            generation: 0,
+            car_number: 1,
+            position,
+            sector,
+            wall_hit,
+        };
+    }
+
+    #[test]
+    fn nothing_crashed_has_no_worst_sector() {
+        let map = CrashMap::new(4);
+        assert_eq!(map.total(), 0);
+        assert_eq!(map.worst_sector(), None);
+    }
+
+    #[test]
+    fn add_counts_each_kind_per_sector() {
+        let mut map = CrashMap::new(3);
+        map.add(event(1, false, vec2(10.0, 10.0)));
+        map.add(event(1, true, vec2(10.0, 10.0)));
+        map.add(event(2, true, vec2(90.0, 10.0)));
+        assert_eq!(map.total(), 3);
+        assert_eq!((map.sectors[1].crashes, map.sectors[1].wall_hits), (1, 1));
+        assert_eq!((map.sectors[2].crashes, map.sectors[2].wall_hits), (0, 1));
+        assert_eq!(map.sectors[1].this_generation, 2);
+        // both events at the same spot went in one cell
+        assert_eq!(map.busiest, 2);
+        assert_eq!(map.cells.len(), 2);
+    }
+
+    #[test]
+    fn negative_positions_get_their_own_cells() {
+        let mut map = CrashMap::new(1);
+        map.add(event(0, false, vec2(-1.0, -1.0)));
+        map.add(event(0, false, vec2(1.0, 1.0)));
+        assert!(map.cells.contains_key(&(-1, -1)));
+        assert!(map.cells.contains_key(&(0, 0)));
+    }
+
+    #[test]
+    fn events_past_the_last_sector_only_go_on_the_heatmap() {
+        let mut map = CrashMap::new(2);
+        map.add(event(5, false, vec2(0.0, 0.0)));
+        assert_eq!(map.total(), 0);
+        assert_eq!(map.busiest, 1);
+    }
+
+    #[test]
+    fn worst_sector_counts_both_kinds() {
+        let mut map = CrashMap::new(3);
+        map.add(event(0, false, Vec2::ZERO));
+        map.add(event(0, false, Vec2::ZERO));
+        map.add(event(2, true, Vec2::ZERO));
+        map.add(event(2, true, Vec2::ZERO));
+        map.add(event(2, false, Vec2::ZERO));
+        assert_eq!(map.worst_sector(), Some((2, 3)));
+    }
+
+    #[test]
+    fn sector_table_has_a_row_per_sector() {
+        let mut map = CrashMap::new(3);
+        map.add(event(0, false, Vec2::ZERO));
+        map.add(event(2, true, Vec2::ZERO));
+        map.add(event(2, true, Vec2::ZERO));
+        map.add(event(2, false, Vec2::ZERO));
+        let table = map.sector_table();
+        let lines: Vec<&str> = table.lines().collect();
+        assert_eq!(
+            lines,
+            [
+                SECTORS_HEADER,
+                "0,1,0,0.250,1",
+                "1,0,0,0.000,0",
+                "2,1,2,0.750,3"
+            ]
+        );
+        assert!(SECTORS_HEADER.ends_with(",this_generation"));
+    }
+
+    #[test]
+    fn end_generation_keeps_the_run_totals() {
+        let mut map = CrashMap::new(2);
+        map.add(event(0, false, Vec2::ZERO));
+        map.add(event(1, true, Vec2::ZERO));
+        map.end_generation();
+        assert!(map.unsaved.is_empty());
+        assert_eq!(map.sectors[0].this_generation, 0);
+        assert_eq!(map.sectors[1].this_generation, 0);
+        assert_eq!(map.total(), 2);
+        assert_eq!(map.busiest, 2);
+    }
+
+    #[test]
+    fn empty_sector_table_has_no_shares() {
+        let table = CrashMap::new(2).sector_table();
+        assert_eq!(table.lines().nth(1), Some("0,0,0,0.000,0"));
+    }
+}
@@ -0,0 +1,303 @@
+use crate::car::{Car, Controller, NUM_INPUTS};
+use crate::car_spec::CarClass;
+use crate::config::SimConfig;
+use crate::ghost::*;
+use crate::leaderboard::Leaderboard;
+use crate::network::Network;
+use crate::population::{Population, CHAMPION_PATH};
+use crate::records::LapRecord;
+use crate::track::*;
+use crate::utils::format_date_time;
+use macroquad::prelude::*;
+use std::fs;
+use std::fs::File;
+use std::io::prelude::*;
+use std::io::BufWriter;
+
+// what the network would have seen and what the person did, one row per tick
+// every session gets its own file named after when it started
+const HUMAN_DATA_DIR: &str = "human_driving";
+
+// car numbers used on the lap board
+const PLAYER_NUMBER: usize = 1;
+const CHAMPION_NUMBER: usize = 2;
+// a drive session is one long generation, so personal best sectors last the whole session
+const SESSION_GENERATION: usize = 0;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Opponents {
+    None,
+    Champion,   // the last saved best network
+    Population, // the whole population training alongside
+}
+
+// a person driving a car with the arrow keys
+pub struct DriveSession {
+    track: Track,
+    player: Car,
+    champion: Option<Car>,
+    population: Option<Population>,
+    leaderboard: Leaderboard,
+    ghost: Option<Ghost>,
+    texture: Option<Texture2D>, // the ghost is drawn with the player's sprite
+    config: SimConfig,
+    ticks: u32,
+    data_file: Option<BufWriter<File>>,
+}
+
+impl DriveSession {
+    // None if the track in the config doesn't exist
+    pub fn new(config: SimConfig, opponents: Opponents) -> Option<Self> {
+        let Some(mut track) = Track::from_id(&config.track_id) else {
+            println!("[drive.rs] - Unknown track {}", config.track_id);
+            return None;
+        };
+        let ghost = Ghost::load(&track.id);
+        if let Some(ghost) = &ghost {
+            track.set_racing_line(ghost.get_line());
+        }
+        let class: CarClass = config.car_classes[0].load_class();
+
+        let mut player = Car::new(track.get_start_pos(), PLAYER_NUMBER, &class);
+        player.controller = Controller::Keyboard;
+        // only population cars draw trails, these two drive on until the session ends
+        player.trail_on = false;
+
+        let mut champion = None;
+        if opponents == Opponents::Champion {
+            match Network::load(CHAMPION_PATH) {
+                Some(brain) => {
+                    let mut car = Car::new(track.get_start_pos(), CHAMPION_NUMBER, &class);
+                    car.brain = brain;
+                    car.trail_on = false;
+                    champion = Some(car);
+                }
+                None => println!("[drive.rs] - No champion saved at {}", CHAMPION_PATH),
+            }
+        }
+
+        let population = match opponents {
+            Opponents::Population => Population::opponents(config.clone()),
+            _ => None,
+        };
+
+        // header for the driving data
+        let now = macroquad::miniquad::date::now() as u64;
+        let path = format!("{}/{}.csv", HUMAN_DATA_DIR, format_date_time(now));
+        let result = fs::create_dir_all(HUMAN_DATA_DIR)
+            .and_then(|_| File::create(&path))
+            .and_then(|file| {
+                let mut file = BufWriter::new(file);
+                let mut header = "tick".to_string();
+                for i in 0..NUM_INPUTS {
+                    header += &format!(",input_{}", i);
+                }
+                header += ",throttle,steer,brake";
+                writeln!(file, "{}", header)?;
+                Ok(file)
+            });
+        let data_file = match result {
+            Ok(file) => Some(file),
+            Err(e) => {
+                println!("[drive.rs] - Couldn't create {}: {}", path, e);
+                None
+            }
+        };
+
+        return Some(Self {
+            track,
+            player,
+            champion,
+            population,
+            leaderboard: Leaderboard::default().show_lap_numbers(),
+            ghost,
+            texture: class.texture.clone(),
+            config,
+            ticks: 0,
+            data_file,
+        });
+    }
+
+    pub fn update(&mut self) {
+        let dt = get_frame_time();
+
+        // start again from the line
+        if is_key_pressed(KeyCode::R) {
+            self.player.reset(self.track.get_start_pos());
+            if let Some(champion) = &mut self.champion {
+                champion.reset(self.track.get_start_pos());
+            }
+        }
+
+        if let Some(pop) = &mut self.population {
+            pop.update();
+        }
+
+        if let Some(champion) = &mut self.champion {
+            champion.update(&self.track, dt, self.config.physics_model);
+            champion.apply_track_limits(
+                &self.track,
+                self.config.collision_mode,
+                self.config.off_track_rule,
+            );
+            if champion.just_lapped {
+                let invalid = champion.last_lap_invalid.map(|reason| reason.to_string());
+                let place = self
+                    .leaderboard
+                    .submit(champion.get_lap_entry(SESSION_GENERATION), invalid);
+                if let (true, Some(ghost)) = (
+                    record_if_faster(&mut self.ghost, &self.track.id, champion),
+                    &self.ghost,
+                ) {
+                    self.track.set_racing_line(ghost.get_line());
+                }
+                if place == Some(0) {
+                    LapRecord::from_car(&self.track.id, self.config.seed, champion.laps, champion)
+                        .save();
+                }
+            }
+        }
+
+        // what the car could see before the person reacted to it
+        let senses = self.player.sense(&self.track);
+
+        self.player
+            .update(&self.track, dt, self.config.physics_model);
+        self.player.apply_track_limits(
+            &self.track,
+            self.config.collision_mode,
+            self.config.off_track_rule,
+        );
+
+        if self.player.just_lapped {
+            let invalid = self
+                .player
+                .last_lap_invalid
+                .map(|reason| reason.to_string());
+            let place = self
+                .leaderboard
+                .submit(self.player.get_lap_entry(SESSION_GENERATION), invalid);
+            if let (true, Some(ghost)) = (
+                record_if_faster(&mut self.ghost, &self.track.id, &self.player),
+                &self.ghost,
+            ) {
+                self.track.set_racing_line(ghost.get_line());
+            }
+            if place == Some(0) {
+                // no genome marks the lap as driven by a person
+                let mut record = LapRecord::from_car(
+                    &self.track.id,
+                    self.config.seed,
+                    self.player.laps,
+                    &self.player,
+                );
+                record.genome = String::new();
+                record.save();
+            }
+        }
+
+        if !self.player.crashed {
+            if let Some(file) = &mut self.data_file {
+                let controls = self.player.get_controls();
+                let mut row = format!("{}", self.ticks);
+                for value in senses.iter() {
+                    row += &format!(",{}", value);
+                }
+                row += &format!(
+                    ",{},{},{}",
+                    controls.throttle, controls.steer, controls.brake
+                );
+                // stop logging rather than fill the terminal every tick
+                if let Err(e) = writeln!(file, "{}", row) {
+                    println!("[drive.rs] - Couldn't write driving data: {}", e);
+                    self.data_file = None;
+                }
+            }
+        }
+
+        self.ticks += 1;
+    }
+
+    pub fn get_track(&self) -> &Track {
+        return &self.track;
+    }
+
+    pub fn get_player_pos(&self) -> Vec2 {
+        return self.player.get_centre();
+    }
+
+    // drawn through the camera
+    pub fn draw_world(&self) {
+        match &self.population {
+            Some(pop) => pop.draw_world(),
+            None => self.track.draw(),
+        }
+
+        // the ghost keeps pace with the player's current lap
+        if let (Some(ghost), Some(texture)) = (&self.ghost, &self.texture) {
+            ghost.draw(self.player.get_current_lap_time(), texture);
+        }
+
+        if let Some(champion) = &self.champion {
+            champion.draw(true);
+        }
+        self.player.draw(false);
+    }
+
+    // drawn in screen pixels on top of the world
+    pub fn draw_hud(&self) {
+        if let Some(pop) = &self.population {
+            pop.draw_hud();
+        }
+
+        // lap times
+        let best_text = match self.leaderboard.get_fastest() {
+            None => "-".to_string(),
+            Some(best) if best.car_number == PLAYER_NUMBER => {
+                format!("{:.3}s (you)", best.lap_seconds)
+            }
+            Some(best) => format!("{:.3}s (champion)", best.lap_seconds),
+        };
+        draw_text(
+            &format!("Lap {}", self.player.laps + 1),
+            20.0,
+            40.0,
+            35.0,
+            BLACK,
+        );
+        draw_text(
+            &format!("Current: {:.3}s", self.player.get_current_lap_seconds()),
+            20.0,
+            75.0,
+            30.0,
+            BLACK,
+        );
+        draw_text(
+            &match self.player.last_lap_invalid {
+                Some(reason) => format!(
+                    "Last: {:.3}s (invalid: {})",
+                    self.player.lap_seconds, reason
+                ),
+                None => format!("Last: {:.3}s", self.player.lap_seconds),
+            },
+            20.0,
+            105.0,
+            30.0,
+            BLACK,
+        );
+        draw_text(&format!("Best: {}", best_text), 20.0, 135.0, 30.0, BLACK);
+
+        // every lap driven this session, numbered by each car's lap count
+        self.leaderboard.draw(20.0, 160.0);
+
+        if self.player.crashed {
+            draw_text(
+                "Crashed! Press R to restart",
+                screen_width() / 2.0 - 250.0,
+                screen_height() / 2.0,
+                45.0,
+                RED,
+            );
+        }
+    }
+}
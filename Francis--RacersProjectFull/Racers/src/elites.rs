@@ -0,0 +1,316 @@
+use crate::car::Car;
+use crate::lineage::GenomeId;
+use crate::network::Network;
+use macroquad::prelude::*;
+use std::fs;
+
+pub const ELITES_SIZE: usize = 10;
+// the networks go in here inside the run's folder, one file per place
+const ELITES_DIR: &str = "elites";
+const TABLE_NAME: &str = "elites.csv";
+const TABLE_HEADER: &str =
+    "rank,genome,generation,car,class,evaluations,mean_fitness,best_fitness,worst_fitness,best_lap";
+const PANEL_WIDTH: f32 = 640.0;
+const ROW_HEIGHT: f32 = 26.0;
+
+// a genome good enough to be remembered after its generation ends
+pub struct Elite {
+    pub genome_id: GenomeId,
+    pub brain: Network,
+    pub class_name: String, // raced again in the same class of car
+    pub generation: usize,  // the generation it first raced in
+    pub car_number: usize,
+    pub scores: Vec<i32>, // final fitness from every generation it raced in
+    pub best_lap: Option<f32>,
+}
+
+// the best genomes of the whole run, not just the last generation
+// ranked by mean fitness so a lucky score counts for less once the genome races again
+// (the lap records screen is the hall of fame in hall_of_fame.rs, this one is for networks)
+pub struct Elites {
+    entries: Vec<Elite>,
+    pub visible: bool,
+}
+
+impl Default for Elites {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Elite {
+    pub fn fitness(&self) -> f64 {
+        let total: f64 = self.scores.iter().map(|s| *s as f64).sum();
+        return total / self.scores.len().max(1) as f64;
+    }
+}
+
+impl Elites {
+    pub fn new() -> Self {
+        Self {
+            entries: vec![],
+            visible: false,
+        }
+    }
+
+    // best first
+    pub fn get_entries(&self) -> &[Elite] {
+        return &self.entries;
+    }
+
+    pub fn len(&self) -> usize {
+        return self.entries.len();
+    }
+
+    pub fn is_empty(&self) -> bool {
+        return self.entries.is_empty();
+    }
+
+    // the mean over every time the genome raced, None if it isn't one of the elites
+    pub fn fitness_of(&self, id: GenomeId) -> Option<f64> {
+        return self
+            .entries
+            .iter()
+            .find(|e| e.genome_id == id)
+            .map(|e| e.fitness());
+    }
+
+    // a car at the end of a generation, an elite that raced again gets the score added
+    // to its others, anyone else gets in if they beat the worst of the elites
+    pub fn submit(&mut self, car: &Car, generation: usize, fitness: i32) {
+        self.submit_genome(car.genome_id, fitness, car.best_lap_seconds, || Elite {
+            genome_id: car.genome_id,
+            brain: car.brain.clone(),
+            class_name: car.spec.name.clone(),
+            generation,
+            car_number: car.number,
+            scores: vec![fitness],
+            best_lap: car.best_lap_seconds,
+        });
+    }
+
+    // the ranking on its own, new_elite is only called once the genome has got in
+    // so the network isn't copied for every car
+    fn submit_genome(
+        &mut self,
+        genome_id: GenomeId,
+        fitness: i32,
+        best_lap: Option<f32>,
+        new_elite: impl FnOnce() -> Elite,
+    ) {
+        if let Some(elite) = self.entries.iter_mut().find(|e| e.genome_id == genome_id) {
+            elite.scores.push(fitness);
+            if let Some(lap) = best_lap {
+                elite.best_lap = Some(elite.best_lap.map_or(lap, |best| best.min(lap)));
+            }
+        } else {
+            let full = self.entries.len() >= ELITES_SIZE;
+            if full
+                && self
+                    .entries
+                    .last()
+                    .is_some_and(|e| e.fitness() >= fitness as f64)
+            {
+                return;
+            }
+            self.entries.push(new_elite());
+        }
+
+        self.entries
+            .sort_by(|a, b| b.fitness().total_cmp(&a.fitness()));
+        self.entries.truncate(ELITES_SIZE);
+    }
+
+    // rewrites the table and the networks, elites/rank01.net is the best
+    pub fn save(&self, run_dir: &str) {
+        let mut text = format!("{}\n", TABLE_HEADER);
+        for (i, elite) in self.entries.iter().enumerate() {
+            let best = elite.scores.iter().max().unwrap_or(&0);
+            let worst = elite.scores.iter().min().unwrap_or(&0);
+            let lap = elite
+                .best_lap
+                .map_or(String::new(), |t| format!("{:.3}", t));
+            text += &format!(
+                "{},{},{},{},{},{},{:.1},{},{},{}\n",
+                i + 1,
+                elite.genome_id,
+                elite.generation,
+                elite.car_number,
+                elite.class_name,
+                elite.scores.len(),
+                elite.fitness(),
+                best,
+                worst,
+                lap
+            );
+        }
+        let path = format!("{}/{}", run_dir, TABLE_NAME);
+        if let Err(e) = fs::write(&path, text) {
+            println!("[elites.rs] - Couldn't save {}: {}", path, e);
+        }
+
+        let dir = format!("{}/{}", run_dir, ELITES_DIR);
+        if let Err(e) = fs::create_dir_all(&dir) {
+            println!("[elites.rs] - Couldn't create {}: {}", dir, e);
+            return;
+        }
+        for (i, elite) in self.entries.iter().enumerate() {
+            elite.brain.save(&format!("{}/rank{:02}.net", dir, i + 1));
+        }
+    }
+
+    pub fn update(&mut self) {
+        if is_key_pressed(KeyCode::E) {
+            self.visible = !self.visible;
+        }
+    }
+
+    // a table along the bottom of the screen, above the timer bar
+    pub fn draw(&self) {
+        if !self.visible {
+            return;
+        }
+        let height = ROW_HEIGHT * (ELITES_SIZE as f32 + 2.5);
+        let x = (screen_width() - PANEL_WIDTH) / 2.0;
+        let y = screen_height() - 80.0 - height;
+        draw_rectangle(x, y, PANEL_WIDTH, height, color_u8!(255, 255, 255, 230));
+        draw_rectangle_lines(x, y, PANEL_WIDTH, height, 2.0, BLACK);
+        draw_text(
+            "Elites (E), ranked by mean fitness over every race",
+            x + 10.0,
+            y + ROW_HEIGHT,
+            22.0,
+            BLACK,
+        );
+
+        let columns = [10.0, 50.0, 140.0, 200.0, 290.0, 380.0, 510.0];
+        let headings = ["#", "Genome", "Gen", "Races", "Mean", "Scores", "Best lap"];
+        for (column, heading) in columns.iter().zip(headings.iter()) {
+            draw_text(heading, x + column, y + ROW_HEIGHT * 2.0, 20.0, BLACK);
+        }
+
+        for (i, elite) in self.entries.iter().enumerate() {
+            let best = elite.scores.iter().max().unwrap_or(&0);
+            let worst = elite.scores.iter().min().unwrap_or(&0);
+            let row = [
+                (i + 1).to_string(),
+                elite.genome_id.to_string(),
+                elite.generation.to_string(),
+                elite.scores.len().to_string(),
+                format!("{:.0}", elite.fitness()),
+                format!("{} to {}", worst, best),
+                elite
+                    .best_lap
+                    .map_or("-".to_string(), |t| format!("{:.3}s", t)),
+            ];
+            let row_y = y + ROW_HEIGHT * (i as f32 + 3.0);
+            for (column, text) in columns.iter().zip(row.iter()) {
+                draw_text(text, x + column, row_y, 20.0, BLACK);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn submit(elites: &mut Elites, id: GenomeId, fitness: i32, best_lap: Option<f32>) {
+        elites.submit_genome(id, fitness, best_lap, || Elite {
+            genome_id: id,
+            brain: Network::new_empty(),
+            class_name: "GT".to_string(),
+            generation: 0,
+            car_number: 1,
+            scores: vec![fitness],
+            best_lap,
+        });
+    }
+
+    fn ranking(elites: &Elites) -> Vec<GenomeId> {
+        return elites.get_entries().iter().map(|e| e.genome_id).collect();
+    }
+
+    #[test]
+    fn entries_are_ranked_best_first() {
+        let mut elites = Elites::new();
+        submit(&mut elites, 0, 100, None);
+        submit(&mut elites, 1, 300, None);
+        submit(&mut elites, 2, 200, None);
+        assert_eq!(ranking(&elites), [1, 2, 0]);
+        assert_eq!(elites.fitness_of(2), Some(200.0));
+        assert_eq!(elites.fitness_of(9), None);
+    }
+
+    #[test]
+    fn a_full_table_keeps_the_best() {
+        let mut elites = Elites::new();
+        for id in 0..ELITES_SIZE as GenomeId {
+            submit(&mut elites, id, 100 + id as i32, None);
+        }
+        assert_eq!(elites.len(), ELITES_SIZE);
+
+        // no better than the worst, so it stays out
+        submit(&mut elites, 50, 100, None);
+        assert_eq!(elites.fitness_of(50), None);
+
+        // better than the worst, which drops off the end
+        submit(&mut elites, 51, 105, None);
+        assert_eq!(elites.len(), ELITES_SIZE);
+        assert_eq!(elites.fitness_of(51), Some(105.0));
+        assert_eq!(elites.fitness_of(0), None);
+        assert_eq!(ranking(&elites).last(), Some(&1));
+    }
+
+    #[test]
+    fn racing_again_averages_the_scores() {
+        let mut elites = Elites::new();
+        submit(&mut elites, 0, 300, None);
+        submit(&mut elites, 1, 200, None);
+        // a lucky first score gets pulled down
+        submit(&mut elites, 0, 50, None);
+        assert_eq!(elites.len(), 2);
+        assert_eq!(elites.fitness_of(0), Some(175.0));
+        assert_eq!(ranking(&elites), [1, 0]);
+        assert_eq!(elites.get_entries()[1].scores, [300, 50]);
+    }
+
+    #[test]
+    fn an_elite_can_fall_out_after_a_bad_race() {
+        let mut elites = Elites::new();
+        for id in 0..ELITES_SIZE as GenomeId {
+            submit(&mut elites, id, 1000, None);
+        }
+        submit(&mut elites, 3, -1000, None);
+        // still in the table, just last
+        assert_eq!(ranking(&elites).last(), Some(&3));
+        submit(&mut elites, 99, 10, None);
+        assert_eq!(elites.fitness_of(3), None);
+        assert_eq!(ranking(&elites).last(), Some(&99));
+    }
+
+    #[test]
+    fn best_lap_keeps_the_fastest() {
+        let mut elites = Elites::new();
+        submit(&mut elites, 0, 100, None);
+        submit(&mut elites, 0, 100, Some(42.0));
+        submit(&mut elites, 0, 100, Some(45.0));
+        assert_eq!(elites.get_entries()[0].best_lap, Some(42.0));
+        submit(&mut elites, 0, 100, Some(40.5));
+        assert_eq!(elites.get_entries()[0].best_lap, Some(40.5));
+    }
+
+    #[test]
+    fn nothing_raced_is_zero_fitness() {
+        let elite = Elite {
+            genome_id: 0,
+            brain: Network::new_empty(),
+            class_name: String::new(),
+            generation: 0,
+            car_number: 1,
+            scores: vec![],
+            best_lap: None,
+        };
+        assert_eq!(elite.fitness(), 0.0);
+    }
+}
@@ -0,0 +1,566 @@
+use macroquad::prelude::*;
+
+// tolerance used for the parallel and on-segment checks
+// track coordinates are in pixels so anything this small is effectively zero
+pub const EPSILON: f32 = 1e-5;
+
+// twice the signed area of the triangle a, b, c
+// positive if c is to the left of a -> b, negative if to the right and zero if collinear
+pub fn orientation(a: Vec2, b: Vec2, c: Vec2) -> f32 {
+    return (b - a).perp_dot(c - a);
+}
+
+pub fn closest_point_on_segment(point: Vec2, a: Vec2, b: Vec2) -> Vec2 {
+    let ab = b - a;
+    let length_sq = ab.length_squared();
+    if length_sq < EPSILON * EPSILON {
+        // the segment is really just a point
+        return a;
+    }
+
+    let t = clamp((point - a).dot(ab) / length_sq, 0.0, 1.0);
+    return a + ab * t;
+}
+
+pub fn point_segment_distance(point: Vec2, a: Vec2, b: Vec2) -> f32 {
+    return point.distance(closest_point_on_segment(point, a, b));
+}
+
+pub fn point_on_segment(point: Vec2, a: Vec2, b: Vec2) -> bool {
+    // f32 rounding grows with the size of the coordinates so the tolerance does too
+    let magnitude = point.abs().max(a.abs()).max(b.abs()).max_element();
+    return point_segment_distance(point, a, b) <= EPSILON * (1.0 + magnitude);
+}
+
+// intersection point of segments p1-p2 and q1-q2
+// parallel segments never intersect, collinear segments that overlap return the
+// overlapping point closest to p1
+pub fn segment_intersection(p1: Vec2, p2: Vec2, q1: Vec2, q2: Vec2) -> Option<Vec2> {
+    let r = p2 - p1;
+    let s = q2 - q1;
+    let denom = r.perp_dot(s);
+    let scale = 1.0 + r.length() * s.length();
+
+    if denom.abs() <= EPSILON * scale {
+        // parallel, collinear or degenerate
+        return collinear_overlap(p1, p2, q1, q2);
+    }
+
+    let to_q = q1 - p1;
+    let t = to_q.perp_dot(s) / denom;
+    let u = to_q.perp_dot(r) / denom;
+
+    // allow a tiny bit of slack so hits exactly on an end point aren't lost to rounding
+    let range = -EPSILON..=1.0 + EPSILON;
+    if range.contains(&t) && range.contains(&u) {
+        return Some(p1 + r * clamp(t, 0.0, 1.0));
+    }
+    None
+}
+
+fn collinear_overlap(p1: Vec2, p2: Vec2, q1: Vec2, q2: Vec2) -> Option<Vec2> {
+    // check every end point against the other segment, keep the one closest to p1
+    let mut best: Option<Vec2> = None;
+    let candidates = [
+        (p1, point_on_segment(p1, q1, q2)),
+        (p2, point_on_segment(p2, q1, q2)),
+        (q1, point_on_segment(q1, p1, p2)),
+        (q2, point_on_segment(q2, p1, p2)),
+    ];
+    for (point, touching) in candidates {
+        if touching && best.is_none_or(|b| point.distance(p1) < b.distance(p1)) {
+            best = Some(point);
+        }
+    }
+    return best;
+}
+
+// distance along the ray to the segment a-b, or None if it misses
+// the direction doesn't have to be normalised, the result is in multiples of it
+pub fn ray_segment_intersection(origin: Vec2, direction: Vec2, a: Vec2, b: Vec2) -> Option<f32> {
+    let edge = b - a;
+    let denom = direction.perp_dot(edge);
+    let to_a = a - origin;
+
+    if denom.abs() <= EPSILON * (1.0 + direction.length() * edge.length()) {
+        // parallel, only counts if the segment lies on the ray
+        if orientation(origin, origin + direction, a).abs() > EPSILON * (1.0 + to_a.length()) {
+            return None;
+        }
+        let length_sq = direction.length_squared();
+        if length_sq < EPSILON * EPSILON {
+            return None;
+        }
+        let ta = to_a.dot(direction) / length_sq;
+        let tb = (b - origin).dot(direction) / length_sq;
+        if ta.max(tb) < 0.0 {
+            return None;
+        }
+        return Some(ta.min(tb).max(0.0));
+    }
+
+    let t = to_a.perp_dot(edge) / denom;
+    let u = to_a.perp_dot(direction) / denom;
+
+    if t >= 0.0 && (-EPSILON..=1.0 + EPSILON).contains(&u) {
+        return Some(t);
+    }
+    None
+}
+
+// even-odd containment test, points exactly on an edge count as inside
+pub fn point_in_polygon(point: Vec2, polygon: &[Vec2]) -> bool {
+    if polygon.len() < 3 {
+        return false;
+    }
+
+    let mut inside = false;
+    for i in 0..polygon.len() {
+        let a = polygon[i];
+        let b = polygon[(i + 1) % polygon.len()];
+
+        if point_on_segment(point, a, b) {
+            return true;
+        }
+
+        // half open rule on y so a vertex shared by two edges is only counted once
+        // and horizontal edges are skipped
+        if (a.y > point.y) != (b.y > point.y) {
+            let x_cross = a.x + (point.y - a.y) / (b.y - a.y) * (b.x - a.x);
+            if point.x < x_cross {
+                inside = !inside;
+            }
+        }
+    }
+    return inside;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // small xorshift generator so the property tests are repeatable
+    struct Rng(u64);
+
+    impl Rng {
+        fn next_f32(&mut self) -> f32 {
+            self.0 ^= self.0 << 13;
+            self.0 ^= self.0 >> 7;
+            self.0 ^= self.0 << 17;
+            (self.0 >> 40) as f32 / (1u64 << 24) as f32
+        }
+
+        fn range(&mut self, min: f32, max: f32) -> f32 {
+            min + (max - min) * self.next_f32()
+        }
+
+        fn point(&mut self) -> Vec2 {
+            vec2(self.range(-1000.0, 1000.0), self.range(-1000.0, 1000.0))
+        }
+    }
+
+    const CASES: usize = 5000;
+
+    fn square() -> Vec<Vec2> {
+        vec![
+            vec2(0.0, 0.0),
+            vec2(10.0, 0.0),
+            vec2(10.0, 10.0),
+            vec2(0.0, 10.0),
+        ]
+    }
+
+    #[test]
+    fn crossing_segments_intersect() {
+        let hit = segment_intersection(
+            vec2(0.0, 0.0),
+            vec2(10.0, 10.0),
+            vec2(0.0, 10.0),
+            vec2(10.0, 0.0),
+        );
+        assert_eq!(hit, Some(vec2(5.0, 5.0)));
+    }
+
+    #[test]
+    fn separate_segments_miss() {
+        let hit = segment_intersection(
+            vec2(0.0, 0.0),
+            vec2(1.0, 1.0),
+            vec2(5.0, 0.0),
+            vec2(6.0, -3.0),
+        );
+        assert_eq!(hit, None);
+    }
+
+    #[test]
+    fn parallel_segments_miss() {
+        let hit = segment_intersection(
+            vec2(0.0, 0.0),
+            vec2(10.0, 0.0),
+            vec2(0.0, 1.0),
+            vec2(10.0, 1.0),
+        );
+        assert_eq!(hit, None);
+    }
+
+    #[test]
+    fn collinear_overlap_returns_nearest_point() {
+        let hit = segment_intersection(
+            vec2(0.0, 0.0),
+            vec2(10.0, 0.0),
+            vec2(4.0, 0.0),
+            vec2(20.0, 0.0),
+        );
+        assert_eq!(hit, Some(vec2(4.0, 0.0)));
+
+        // starting inside the other segment gives the start point
+        let hit = segment_intersection(
+            vec2(5.0, 0.0),
+            vec2(10.0, 0.0),
+            vec2(0.0, 0.0),
+            vec2(20.0, 0.0),
+        );
+        assert_eq!(hit, Some(vec2(5.0, 0.0)));
+    }
+
+    #[test]
+    fn collinear_disjoint_misses() {
+        let hit = segment_intersection(
+            vec2(0.0, 0.0),
+            vec2(1.0, 0.0),
+            vec2(2.0, 0.0),
+            vec2(3.0, 0.0),
+        );
+        assert_eq!(hit, None);
+    }
+
+    #[test]
+    fn touching_end_points_intersect() {
+        let hit = segment_intersection(
+            vec2(0.0, 0.0),
+            vec2(5.0, 0.0),
+            vec2(5.0, 0.0),
+            vec2(5.0, 8.0),
+        );
+        assert_eq!(hit, Some(vec2(5.0, 0.0)));
+    }
+
+    #[test]
+    fn vertical_segments_intersect() {
+        // the old line equation divided by x2 - x1 here
+        let hit = segment_intersection(
+            vec2(3.0, -5.0),
+            vec2(3.0, 5.0),
+            vec2(0.0, 1.0),
+            vec2(6.0, 1.0),
+        );
+        assert_eq!(hit, Some(vec2(3.0, 1.0)));
+    }
+
+    #[test]
+    fn degenerate_segments() {
+        // a point lying on a segment
+        let hit = segment_intersection(
+            vec2(2.0, 0.0),
+            vec2(2.0, 0.0),
+            vec2(0.0, 0.0),
+            vec2(4.0, 0.0),
+        );
+        assert_eq!(hit, Some(vec2(2.0, 0.0)));
+
+        // a point off the segment
+        let hit = segment_intersection(
+            vec2(2.0, 1.0),
+            vec2(2.0, 1.0),
+            vec2(0.0, 0.0),
+            vec2(4.0, 0.0),
+        );
+        assert_eq!(hit, None);
+
+        // two points
+        let p = vec2(1.0, 1.0);
+        assert_eq!(segment_intersection(p, p, p, p), Some(p));
+    }
+
+    #[test]
+    fn distance_to_segment() {
+        let a = vec2(0.0, 0.0);
+        let b = vec2(10.0, 0.0);
+        assert_eq!(point_segment_distance(vec2(5.0, 3.0), a, b), 3.0);
+        assert_eq!(point_segment_distance(vec2(-3.0, 4.0), a, b), 5.0);
+        assert_eq!(point_segment_distance(vec2(13.0, -4.0), a, b), 5.0);
+        assert_eq!(point_segment_distance(vec2(7.0, 0.0), a, b), 0.0);
+    }
+
+    #[test]
+    fn distance_to_vertical_segment() {
+        let a = vec2(4.0, 0.0);
+        let b = vec2(4.0, 10.0);
+        assert_eq!(point_segment_distance(vec2(1.0, 5.0), a, b), 3.0);
+        assert_eq!(point_segment_distance(vec2(4.0, 12.0), a, b), 2.0);
+    }
+
+    #[test]
+    fn distance_to_degenerate_segment() {
+        let a = vec2(1.0, 1.0);
+        assert_eq!(point_segment_distance(vec2(4.0, 5.0), a, a), 5.0);
+    }
+
+    #[test]
+    fn ray_hits() {
+        let origin = vec2(0.0, 0.0);
+        let right = vec2(1.0, 0.0);
+        let wall = (vec2(5.0, -1.0), vec2(5.0, 1.0));
+        assert_eq!(
+            ray_segment_intersection(origin, right, wall.0, wall.1),
+            Some(5.0)
+        );
+        assert_eq!(
+            ray_segment_intersection(origin, -right, wall.0, wall.1),
+            None
+        );
+    }
+
+    #[test]
+    fn ray_along_collinear_segment() {
+        let origin = vec2(0.0, 0.0);
+        let right = vec2(1.0, 0.0);
+        let hit = ray_segment_intersection(origin, right, vec2(8.0, 0.0), vec2(3.0, 0.0));
+        assert_eq!(hit, Some(3.0));
+        let hit = ray_segment_intersection(origin, right, vec2(-8.0, 0.0), vec2(-3.0, 0.0));
+        assert_eq!(hit, None);
+        // starting on the segment
+        let hit = ray_segment_intersection(origin, right, vec2(-2.0, 0.0), vec2(3.0, 0.0));
+        assert_eq!(hit, Some(0.0));
+        // parallel but off to the side
+        let hit = ray_segment_intersection(origin, right, vec2(1.0, 1.0), vec2(3.0, 1.0));
+        assert_eq!(hit, None);
+    }
+
+    #[test]
+    fn polygon_containment() {
+        let poly = square();
+        assert!(point_in_polygon(vec2(5.0, 5.0), &poly));
+        assert!(!point_in_polygon(vec2(15.0, 5.0), &poly));
+        assert!(!point_in_polygon(vec2(-1.0, 5.0), &poly));
+        assert!(!point_in_polygon(vec2(5.0, 11.0), &poly));
+    }
+
+    #[test]
+    fn polygon_edges_and_vertices_count_as_inside() {
+        let poly = square();
+        assert!(point_in_polygon(vec2(0.0, 0.0), &poly));
+        assert!(point_in_polygon(vec2(10.0, 5.0), &poly));
+        assert!(point_in_polygon(vec2(5.0, 10.0), &poly));
+    }
+
+    #[test]
+    fn polygon_ray_through_vertex() {
+        // the horizontal ray from this point passes exactly through the vertex at (10, 5)
+        let diamond = vec![
+            vec2(5.0, 0.0),
+            vec2(10.0, 5.0),
+            vec2(5.0, 10.0),
+            vec2(0.0, 5.0),
+        ];
+        assert!(point_in_polygon(vec2(5.0, 5.0), &diamond));
+        assert!(!point_in_polygon(vec2(-5.0, 5.0), &diamond));
+        assert!(!point_in_polygon(vec2(12.0, 5.0), &diamond));
+    }
+
+    #[test]
+    fn polygon_concave() {
+        // a U shape
+        let poly = vec![
+            vec2(0.0, 0.0),
+            vec2(3.0, 0.0),
+            vec2(3.0, 8.0),
+            vec2(7.0, 8.0),
+            vec2(7.0, 0.0),
+            vec2(10.0, 0.0),
+            vec2(10.0, 10.0),
+            vec2(0.0, 10.0),
+        ];
+        assert!(point_in_polygon(vec2(1.0, 1.0), &poly));
+        assert!(!point_in_polygon(vec2(5.0, 4.0), &poly));
+        assert!(point_in_polygon(vec2(5.0, 9.0), &poly));
+    }
+
+    #[test]
+    fn polygon_degenerate() {
+        assert!(!point_in_polygon(vec2(0.0, 0.0), &[]));
+        assert!(!point_in_polygon(
+            vec2(1.0, 0.0),
+            &[vec2(0.0, 0.0), vec2(2.0, 0.0)]
+        ));
+    }
+
+    #[test]
+    fn property_intersection_is_symmetric() {
+        let mut rng = Rng(0x2545F4914F6CDD1D);
+        for _ in 0..CASES {
+            let (p1, p2, q1, q2) = (rng.point(), rng.point(), rng.point(), rng.point());
+            let forward = segment_intersection(p1, p2, q1, q2);
+            let backward = segment_intersection(q1, q2, p1, p2);
+            assert_eq!(forward.is_some(), backward.is_some());
+            if let (Some(f), Some(b)) = (forward, backward) {
+                assert!(f.distance(b) < 1e-2, "{f} vs {b}");
+            }
+        }
+    }
+
+    #[test]
+    fn property_intersection_lies_on_both_segments() {
+        let mut rng = Rng(0x9E3779B97F4A7C15);
+        for _ in 0..CASES {
+            let (p1, p2, q1, q2) = (rng.point(), rng.point(), rng.point(), rng.point());
+            if let Some(hit) = segment_intersection(p1, p2, q1, q2) {
+                assert!(hit.is_finite());
+                assert!(point_segment_distance(hit, p1, p2) < 1e-2);
+                assert!(point_segment_distance(hit, q1, q2) < 1e-2);
+            }
+        }
+    }
+
+    #[test]
+    fn property_intersection_matches_orientation_test() {
+        // two segments properly cross exactly when each separates the other's end points
+        let mut rng = Rng(0xD1B54A32D192ED03);
+        for _ in 0..CASES {
+            let (p1, p2, q1, q2) = (rng.point(), rng.point(), rng.point(), rng.point());
+            let d1 = orientation(q1, q2, p1);
+            let d2 = orientation(q1, q2, p2);
+            let d3 = orientation(p1, p2, q1);
+            let d4 = orientation(p1, p2, q2);
+            let margin = 1.0;
+            let crosses = d1 * d2 < -margin && d3 * d4 < -margin;
+            let apart = d1 * d2 > margin || d3 * d4 > margin;
+            let hit = segment_intersection(p1, p2, q1, q2);
+            if crosses {
+                assert!(hit.is_some());
+            }
+            if apart {
+                assert!(hit.is_none());
+            }
+        }
+    }
+
+    #[test]
+    fn property_collinear_and_parallel_never_nan() {
+        let mut rng = Rng(0xA0761D6478BD642F);
+        for _ in 0..CASES {
+            let a = rng.point();
+            let dir = rng.point();
+            let offset = if rng.next_f32() < 0.5 {
+                Vec2::ZERO
+            } else {
+                dir.perp() * 0.01
+            };
+            let p1 = a + dir * rng.range(-1.0, 1.0);
+            let p2 = a + dir * rng.range(-1.0, 1.0);
+            let q1 = a + offset + dir * rng.range(-1.0, 1.0);
+            let q2 = a + offset + dir * rng.range(-1.0, 1.0);
+            if let Some(hit) = segment_intersection(p1, p2, q1, q2) {
+                assert!(hit.is_finite());
+            }
+            let distance = point_segment_distance(q1, p1, p2);
+            assert!(distance.is_finite());
+        }
+    }
+
+    #[test]
+    fn property_closest_point_is_closest() {
+        let mut rng = Rng(0xE7037ED1A0B428DB);
+        for _ in 0..CASES {
+            let (p, a, b) = (rng.point(), rng.point(), rng.point());
+            let distance = point_segment_distance(p, a, b);
+            assert!(distance <= p.distance(a) + 1e-3);
+            assert!(distance <= p.distance(b) + 1e-3);
+            // no sampled point on the segment is closer
+            for step in 0..=10 {
+                let sample = a.lerp(b, step as f32 / 10.0);
+                assert!(distance <= p.distance(sample) + 1e-2);
+            }
+        }
+    }
+
+    #[test]
+    fn property_ray_agrees_with_segment_test() {
+        let mut rng = Rng(0x8EBC6AF09C88C6E3);
+        for _ in 0..CASES {
+            let origin = rng.point();
+            let direction = Vec2::from_angle(rng.range(0.0, std::f32::consts::TAU));
+            let (a, b) = (rng.point(), rng.point());
+            let far = origin + direction * 5000.0;
+            let ray = ray_segment_intersection(origin, direction, a, b);
+            let segment = segment_intersection(origin, far, a, b);
+            match (ray, segment) {
+                (Some(t), Some(hit)) => assert!((origin + direction * t).distance(hit) < 1e-1),
+                (None, None) => {}
+                // near misses at the very end of the segments can go either way
+                (Some(t), None) => {
+                    assert!(point_segment_distance(origin + direction * t, a, b) < 1e-1)
+                }
+                (None, Some(hit)) => {
+                    assert!(hit.distance(a) < 1e-1 || hit.distance(b) < 1e-1)
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn property_polygon_contains_convex_combinations() {
+        let mut rng = Rng(0x589965CC75374CC3);
+        for _ in 0..CASES / 10 {
+            // a random convex polygon from points on a circle
+            let centre = rng.point();
+            let radius = rng.range(10.0, 300.0);
+            let sides = 3 + (rng.next_f32() * 10.0) as usize;
+            let mut angles: Vec<f32> = (0..sides)
+                .map(|_| rng.range(0.0, std::f32::consts::TAU))
+                .collect();
+            angles.sort_by(|a, b| a.partial_cmp(b).unwrap());
+            let poly: Vec<Vec2> = angles
+                .iter()
+                .map(|a| centre + Vec2::from_angle(*a) * radius)
+                .collect();
+
+            for _ in 0..20 {
+                let a = poly[(rng.next_f32() * sides as f32) as usize % sides];
+                let b = poly[(rng.next_f32() * sides as f32) as usize % sides];
+                let c = poly[(rng.next_f32() * sides as f32) as usize % sides];
+                let (mut w1, mut w2) = (rng.next_f32(), rng.next_f32());
+                if w1 + w2 > 1.0 {
+                    w1 = 1.0 - w1;
+                    w2 = 1.0 - w2;
+                }
+                let inside = a + (b - a) * w1 + (c - a) * w2;
+                assert!(point_in_polygon(inside, &poly));
+            }
+
+            // anything outside the circle is outside the polygon
+            let outside =
+                centre + Vec2::from_angle(rng.range(0.0, std::f32::consts::TAU)) * radius * 1.01;
+            assert!(!point_in_polygon(outside, &poly));
+        }
+    }
+
+    #[test]
+    fn property_polygon_orientation_does_not_matter() {
+        let mut rng = Rng(0x1D8E4E27C47D124F);
+        let mut poly = vec![
+            vec2(0.0, 0.0),
+            vec2(400.0, 50.0),
+            vec2(350.0, 300.0),
+            vec2(200.0, 120.0),
+            vec2(60.0, 280.0),
+        ];
+        let mut reversed = poly.clone();
+        reversed.reverse();
+        poly.rotate_left(2);
+        for _ in 0..CASES {
+            let p = vec2(rng.range(-50.0, 450.0), rng.range(-50.0, 350.0));
+            assert_eq!(point_in_polygon(p, &poly), point_in_polygon(p, &reversed));
+        }
+    }
+}
@@ -0,0 +1,135 @@
+use crate::car::{Car, TraceFrame};
+use macroquad::prelude::*;
+use std::f32::consts::PI;
+use std::fs;
+
+pub const GHOST_DIR: &str = "ghosts";
+
+const GHOST_COLOUR: Color = color_u8!(255, 255, 255, 90);
+
+// the fastest lap driven on a track, replayed as a see-through car
+#[derive(Clone)]
+pub struct Ghost {
+    pub track_id: String,
+    pub lap_time: usize,
+    size: Vec2, // width and length of the car that set it
+    frames: Vec<TraceFrame>,
+}
+
+impl Ghost {
+    pub fn new(track_id: &str, lap_time: usize, size: Vec2, frames: Vec<TraceFrame>) -> Self {
+        Self {
+            track_id: track_id.to_string(),
+            lap_time,
+            size,
+            frames,
+        }
+    }
+
+    pub fn from_car(track_id: &str, car: &Car) -> Self {
+        return Self::new(
+            track_id,
+            car.lap_time,
+            car.get_size(),
+            car.get_last_lap_trace().to_vec(),
+        );
+    }
+
+    fn path(track_id: &str) -> String {
+        return format!("{}/{}.csv", GHOST_DIR, track_id);
+    }
+
+    pub fn save(&self) {
+        // first line is the header, then one x,y,angle line per tick
+        let mut text = format!(
+            "{},{},{},{}\n",
+            self.track_id, self.lap_time, self.size.x, self.size.y
+        );
+        for (pos, angle) in self.frames.iter() {
+            text += &format!("{},{},{}\n", pos.x, pos.y, angle);
+        }
+
+        let result =
+            fs::create_dir_all(GHOST_DIR).and_then(|_| fs::write(Self::path(&self.track_id), text));
+        if let Err(e) = result {
+            println!(
+                "[ghost.rs] - Couldn't save ghost for {}: {}",
+                self.track_id, e
+            );
+        }
+    }
+
+    pub fn load(track_id: &str) -> Option<Self> {
+        let text = fs::read_to_string(Self::path(track_id)).ok()?;
+        let mut lines = text.lines();
+
+        let header: Vec<&str> = lines.next()?.split(',').collect();
+        if header.len() != 4 || header[0] != track_id {
+            return None;
+        }
+        let lap_time = header[1].parse().ok()?;
+        let size = vec2(header[2].parse().ok()?, header[3].parse().ok()?);
+
+        let mut frames = vec![];
+        for line in lines {
+            let values: Vec<f32> = line
+                .split(',')
+                .map(|v| v.parse().ok())
+                .collect::<Option<_>>()?;
+            if values.len() != 3 {
+                return None;
+            }
+            frames.push((vec2(values[0], values[1]), values[2]));
+        }
+        if frames.is_empty() {
+            return None;
+        }
+
+        return Some(Self::new(track_id, lap_time, size, frames));
+    }
+
+    // just the path, for drawing the racing line
+    pub fn get_line(&self) -> Vec<Vec2> {
+        return self.frames.iter().map(|f| f.0).collect();
+    }
+
+    pub fn get_frames(&self) -> &[TraceFrame] {
+        return &self.frames;
+    }
+
+    pub fn draw(&self, tick: usize, texture: &Texture2D) {
+        // loops round so the ghost keeps lapping
+        let (pos, angle) = self.frames[tick % self.frames.len()];
+        let params = DrawTextureParams {
+            dest_size: Some(self.size),
+            rotation: angle + PI / 2.0,
+            ..Default::default()
+        };
+        let corner = pos - self.size / 2.0;
+        draw_texture_ex(texture, corner.x, corner.y, GHOST_COLOUR, params);
+    }
+}
+
+// swaps in the car's last lap if it beat the ghost, returns true if the ghost changed
+// the saved ghost is read again first as another island or session may have beaten
+// this one since it was loaded, and a slower lap mustn't overwrite it
+pub fn record_if_faster(ghost: &mut Option<Ghost>, track_id: &str, car: &Car) -> bool {
+    if car.get_last_lap_trace().is_empty() || car.last_lap_invalid.is_some() {
+        return false;
+    }
+    let mut changed = false;
+    if let Some(saved) = Ghost::load(track_id) {
+        if ghost.as_ref().is_none_or(|g| saved.lap_time < g.lap_time) {
+            *ghost = Some(saved);
+            changed = true;
+        }
+    }
+    if ghost.as_ref().is_some_and(|g| g.lap_time <= car.lap_time) {
+        return changed;
+    }
+
+    let new_ghost = Ghost::from_car(track_id, car);
+    new_ghost.save();
+    *ghost = Some(new_ghost);
+    return true;
+}
@@ -0,0 +1,308 @@
+use crate::stats::GenerationStats;
+use macroquad::prelude::*;
+
+const PANEL_WIDTH: f32 = 520.0;
+const TITLE_HEIGHT: f32 = 30.0;
+const CHART_HEIGHT: f32 = 150.0;
+const CHART_GAP: f32 = 35.0;
+const MARGIN: f32 = 45.0; // room for the axis labels on the left
+
+pub const BEST_COLOUR: Color = color_u8!(0, 160, 0, 255);
+pub const MEAN_COLOUR: Color = color_u8!(30, 90, 220, 255);
+pub const WORST_COLOUR: Color = color_u8!(210, 40, 40, 255);
+pub const LAP_COLOUR: Color = color_u8!(150, 50, 200, 255);
+
+// how a chart maps generations and values onto its area, shared by the in game graph
+// and the svg report so both draw the same lines
+pub struct ChartScale {
+    area: Rect,
+    min: f32,
+    max: f32,
+    generations: usize,
+}
+
+impl ChartScale {
+    // None when no series has a value to draw
+    pub fn new(area: Rect, series: &[(&str, &[Option<f32>], Color)]) -> Option<Self> {
+        let (mut min, mut max) = (f32::MAX, f32::MIN);
+        for value in series.iter().flat_map(|(_, v, _)| v.iter().flatten()) {
+            min = min.min(*value);
+            max = max.max(*value);
+        }
+        if min > max {
+            return None;
+        }
+        if max - min < 1e-3 {
+            // a flat line still needs some height to be drawn in
+            max += 1.0;
+            min -= 1.0;
+        }
+        let generations = series.iter().map(|(_, v, _)| v.len()).max().unwrap_or(0);
+        return Some(Self {
+            area,
+            min,
+            max,
+            generations,
+        });
+    }
+
+    // lap times need a decimal place, fitness doesn't
+    fn label(&self, value: f32) -> String {
+        let decimals = match self.max - self.min < 10.0 {
+            true => 1,
+            false => 0,
+        };
+        return format!("{:.*}", decimals, value);
+    }
+
+    pub fn top_label(&self) -> String {
+        return self.label(self.max);
+    }
+
+    pub fn bottom_label(&self) -> String {
+        return self.label(self.min);
+    }
+
+    pub fn last_generation_label(&self) -> String {
+        return format!("gen {}", self.generations.saturating_sub(1));
+    }
+
+    // where generation i with this value goes, the first is on the left edge
+    // and the last on the right
+    pub fn point(&self, i: usize, value: f32) -> Vec2 {
+        let area = self.area;
+        let fraction_x = i as f32 / (self.generations.max(2) - 1) as f32;
+        let fraction_y = (value - self.min) / (self.max - self.min);
+        return vec2(
+            area.x + fraction_x * area.w,
+            area.y + area.h - fraction_y * area.h,
+        );
+    }
+}
+
+// charts of how each generation did, redrawn from the stats when a generation ends
+// G or clicking the title opens and closes it
+pub struct FitnessGraph {
+    pub open: bool,
+    x: f32,
+    y: f32,
+}
+
+impl FitnessGraph {
+    pub fn new(x: f32, y: f32) -> Self {
+        Self { x, y, open: true }
+    }
+
+    fn title_rect(&self) -> Rect {
+        return Rect::new(self.x, self.y, PANEL_WIDTH, TITLE_HEIGHT);
+    }
+
+    pub fn update(&mut self) {
+        let clicked = is_mouse_button_pressed(MouseButton::Left)
+            && self.title_rect().contains(Vec2::from(mouse_position()));
+        if is_key_pressed(KeyCode::G) || clicked {
+            self.open = !self.open;
+        }
+    }
+
+    pub fn draw(&self, history: &[GenerationStats]) {
+        let title = self.title_rect();
+        draw_rectangle(
+            title.x,
+            title.y,
+            title.w,
+            title.h,
+            color_u8!(220, 220, 220, 220),
+        );
+        draw_rectangle_lines(title.x, title.y, title.w, title.h, 2.0, BLACK);
+        let arrow = match self.open {
+            true => "v",
+            false => ">",
+        };
+        let text = format!("{} Fitness graph (G)", arrow);
+        draw_text(&text, title.x + 10.0, title.y + 21.0, 22.0, BLACK);
+        if !self.open {
+            return;
+        }
+
+        let height = CHART_HEIGHT * 2.0 + CHART_GAP * 2.0 + 10.0;
+        let top = title.y + title.h;
+        draw_rectangle(
+            self.x,
+            top,
+            PANEL_WIDTH,
+            height,
+            color_u8!(255, 255, 255, 180),
+        );
+        draw_rectangle_lines(self.x, top, PANEL_WIDTH, height, 2.0, BLACK);
+        if history.is_empty() {
+            draw_text(
+                "No generations yet",
+                self.x + 10.0,
+                top + 25.0,
+                22.0,
+                DARKGRAY,
+            );
+            return;
+        }
+
+        let fitness_chart = Rect::new(
+            self.x + MARGIN,
+            top + CHART_GAP,
+            PANEL_WIDTH - MARGIN - 15.0,
+            CHART_HEIGHT,
+        );
+        let best: Vec<Option<f32>> = history.iter().map(|s| Some(s.best as f32)).collect();
+        let mean: Vec<Option<f32>> = history.iter().map(|s| Some(s.mean as f32)).collect();
+        let worst: Vec<Option<f32>> = history.iter().map(|s| Some(s.worst as f32)).collect();
+        draw_chart(
+            fitness_chart,
+            "Fitness",
+            &[
+                ("best", &best, BEST_COLOUR),
+                ("mean", &mean, MEAN_COLOUR),
+                ("worst", &worst, WORST_COLOUR),
+            ],
+            true,
+        );
+
+        let lap_chart = Rect::new(
+            fitness_chart.x,
+            fitness_chart.y + CHART_HEIGHT + CHART_GAP,
+            fitness_chart.w,
+            CHART_HEIGHT,
+        );
+        let laps: Vec<Option<f32>> = history.iter().map(|s| s.best_lap).collect();
+        draw_chart(
+            lap_chart,
+            "Best lap (s)",
+            &[("best lap", &laps, LAP_COLOUR)],
+            true,
+        );
+    }
+}
+
+// a line per series against generation, gaps where a generation has no value
+// the legend can be left off when the colours are explained somewhere else
+pub fn draw_chart(area: Rect, title: &str, series: &[(&str, &[Option<f32>], Color)], legend: bool) {
+    draw_rectangle_lines(area.x, area.y, area.w, area.h, 1.0, GRAY);
+
+    // the legend goes above the chart
+    draw_text(title, area.x, area.y - 8.0, 20.0, BLACK);
+    let mut legend_x = area.x + 130.0;
+    for (name, _, colour) in series.iter().filter(|_| legend) {
+        draw_rectangle(legend_x, area.y - 18.0, 10.0, 10.0, *colour);
+        draw_text(name, legend_x + 14.0, area.y - 8.0, 18.0, BLACK);
+        legend_x += 90.0;
+    }
+
+    let Some(scale) = ChartScale::new(area, series) else {
+        draw_text("No data yet", area.x + 10.0, area.y + 25.0, 20.0, DARKGRAY);
+        return;
+    };
+
+    draw_text(
+        &scale.top_label(),
+        area.x - MARGIN + 2.0,
+        area.y + 12.0,
+        16.0,
+        BLACK,
+    );
+    draw_text(
+        &scale.bottom_label(),
+        area.x - MARGIN + 2.0,
+        area.y + area.h,
+        16.0,
+        BLACK,
+    );
+
+    draw_text(
+        &scale.last_generation_label(),
+        area.x + area.w - 50.0,
+        area.y + area.h + 15.0,
+        16.0,
+        BLACK,
+    );
+
+    for (_, values, colour) in series.iter() {
+        for (i, value) in values.iter().enumerate() {
+            let Some(value) = value else {
+                continue;
+            };
+            let a = scale.point(i, *value);
+            match values.get(i + 1).copied().flatten() {
+                Some(next) => {
+                    let b = scale.point(i + 1, next);
+                    draw_line(a.x, a.y, b.x, b.y, 2.0, *colour);
+                }
+                None => draw_circle(a.x, a.y, 2.5, *colour),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const AREA: Rect = Rect {
+        x: 100.0,
+        y: 50.0,
+        w: 400.0,
+        h: 200.0,
+    };
+
+    #[test]
+    fn no_values_has_no_scale() {
+        let empty: Vec<Option<f32>> = vec![None, None];
+        assert!(ChartScale::new(AREA, &[("a", &empty, BLACK)]).is_none());
+        assert!(ChartScale::new(AREA, &[]).is_none());
+    }
+
+    #[test]
+    fn points_span_the_area() {
+        let values = vec![Some(10.0), None, Some(50.0), Some(30.0), Some(20.0)];
+        let scale = ChartScale::new(AREA, &[("a", &values, BLACK)]).unwrap();
+        // the lowest value on the bottom left, the highest at the top
+        assert_eq!(scale.point(0, 10.0), vec2(100.0, 250.0));
+        assert_eq!(scale.point(2, 50.0), vec2(300.0, 50.0));
+        assert_eq!(scale.point(4, 20.0), vec2(500.0, 200.0));
+        assert_eq!(scale.last_generation_label(), "gen 4");
+    }
+
+    #[test]
+    fn every_series_counts_towards_the_range() {
+        let best = vec![Some(100.0), Some(200.0)];
+        let worst = vec![Some(-50.0), Some(0.0), Some(20.0)];
+        let scale =
+            ChartScale::new(AREA, &[("best", &best, BLACK), ("worst", &worst, BLACK)]).unwrap();
+        assert_eq!(scale.top_label(), "200");
+        assert_eq!(scale.bottom_label(), "-50");
+        assert_eq!(scale.last_generation_label(), "gen 2");
+    }
+
+    #[test]
+    fn flat_lines_are_padded_to_the_middle() {
+        let values = vec![Some(42.0), Some(42.0)];
+        let scale = ChartScale::new(AREA, &[("a", &values, BLACK)]).unwrap();
+        assert_eq!(scale.point(0, 42.0).y, AREA.y + AREA.h / 2.0);
+        assert_eq!(scale.top_label(), "43.0");
+        assert_eq!(scale.bottom_label(), "41.0");
+    }
+
+    #[test]
+    fn small_ranges_get_a_decimal_place() {
+        let laps = vec![Some(41.25), Some(39.5)];
+        let scale = ChartScale::new(AREA, &[("lap", &laps, BLACK)]).unwrap();
+        assert_eq!(scale.top_label(), "41.2");
+        assert_eq!(scale.bottom_label(), "39.5");
+    }
+
+    #[test]
+    fn a_single_generation_sits_on_the_left() {
+        let values = vec![Some(5.0)];
+        let scale = ChartScale::new(AREA, &[("a", &values, BLACK)]).unwrap();
+        assert_eq!(scale.point(0, 5.0).x, AREA.x);
+        assert_eq!(scale.last_generation_label(), "gen 0");
+    }
+}
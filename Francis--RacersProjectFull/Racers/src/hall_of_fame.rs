@@ -0,0 +1,174 @@
+use crate::network::Network;
+use crate::population::CHAMPION_PATH;
+use crate::records::*;
+use crate::ui::{Button, Selector};
+use crate::utils::format_date;
+use macroquad::prelude::*;
+
+const ROWS_SHOWN: usize = 15;
+const ROW_HEIGHT: f32 = 30.0;
+const TABLE_TOP: f32 = 220.0;
+const COLUMNS: [f32; 8] = [100.0, 150.0, 280.0, 420.0, 600.0, 680.0, 750.0, 900.0];
+const HEADINGS: [&str; 8] = ["#", "Time", "Class", "Run", "Gen", "Car", "Date", "Driver"];
+
+// main menu screen listing the saved lap records
+pub struct HallOfFame {
+    db: RecordDb,
+    tracks: Vec<String>,
+    track_selector: Selector,
+    view_selector: Selector,
+    champion_button: Button,
+    selected: Option<usize>,
+    message: String,
+}
+
+impl Default for HallOfFame {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl HallOfFame {
+    pub fn new() -> Self {
+        let db = RecordDb::load();
+        let mut tracks = db.track_ids();
+        if tracks.is_empty() {
+            tracks.push("-".to_string());
+        }
+        let track_names: Vec<&str> = tracks.iter().map(|t| t.as_str()).collect();
+
+        Self {
+            track_selector: Selector::new(330.0, 110.0, 250.0, 40.0, &track_names, 0),
+            view_selector: Selector::new(
+                850.0,
+                110.0,
+                250.0,
+                40.0,
+                &["Fastest laps", "Best per run", "Best per class"],
+                0,
+            ),
+            champion_button: Button::new(
+                100.0,
+                700.0,
+                400.0,
+                70.0,
+                "Race selected".to_string(),
+                LIGHTGRAY,
+            ),
+            db,
+            tracks,
+            selected: None,
+            message: String::new(),
+        }
+    }
+
+    fn shown_records(&self) -> Vec<&LapRecord> {
+        let track_id = &self.tracks[self.track_selector.index];
+        let records = match self.view_selector.index {
+            0 => self.db.on_track(track_id),
+            1 => self.db.best_per_run(track_id),
+            _ => self.db.best_by_class(track_id),
+        };
+        return records.into_iter().take(ROWS_SHOWN).collect();
+    }
+
+    pub fn update(&mut self) {
+        let track = self.track_selector.index;
+        let view = self.view_selector.index;
+        self.track_selector.update();
+        self.view_selector.update();
+        if track != self.track_selector.index || view != self.view_selector.index {
+            self.selected = None;
+        }
+
+        // click a row to select it
+        if is_mouse_button_pressed(MouseButton::Left) {
+            let (x, y) = mouse_position();
+            let row = ((y - TABLE_TOP) / ROW_HEIGHT).floor();
+            if x >= COLUMNS[0] && row >= 0.0 && (row as usize) < self.shown_records().len() {
+                self.selected = Some(row as usize);
+            }
+        }
+
+        // the selected record's network becomes the champion raced in drive mode
+        if self.champion_button.check_pressed() {
+            self.champion_button.reset();
+            let records = self.shown_records();
+            self.message = match self.selected.map(|i| records[i]) {
+                None => "Select a record first".to_string(),
+                Some(record) if record.is_human() => "That lap was driven by a person".to_string(),
+                Some(record) => match Network::from_text(&record.genome) {
+                    Some(network) => {
+                        network.save(CHAMPION_PATH);
+                        "Saved as the champion, drive against it from the menu".to_string()
+                    }
+                    None => "The saved genome couldn't be read".to_string(),
+                },
+            };
+        }
+    }
+
+    pub fn draw(&self) {
+        draw_text("Hall of Fame", 100.0, 80.0, 60.0, BLACK);
+        draw_text("Track:", 100.0, 138.0, 30.0, BLACK);
+        draw_text("Show:", 620.0, 138.0, 30.0, BLACK);
+        self.track_selector.draw();
+        self.view_selector.draw();
+
+        let track_id = &self.tracks[self.track_selector.index];
+        if let Some(best) = self.db.best_on_track(track_id) {
+            let text = format!(
+                "Track record: {:.3}s by car {} ({}) in run {}",
+                best.lap_seconds, best.car_number, best.class, best.run_id
+            );
+            draw_text(&text, 100.0, 180.0, 25.0, BLACK);
+        }
+
+        for (column, heading) in COLUMNS.iter().zip(HEADINGS.iter()) {
+            draw_text(heading, *column, TABLE_TOP - 10.0, 25.0, BLACK);
+        }
+
+        let records = self.shown_records();
+        if records.is_empty() {
+            draw_text(
+                "No laps recorded yet",
+                100.0,
+                TABLE_TOP + 25.0,
+                25.0,
+                DARKGRAY,
+            );
+        }
+        for (i, record) in records.iter().enumerate() {
+            let y = TABLE_TOP + ROW_HEIGHT * i as f32;
+            if self.selected == Some(i) {
+                draw_rectangle(
+                    COLUMNS[0] - 10.0,
+                    y,
+                    1000.0,
+                    ROW_HEIGHT,
+                    color_u8!(255, 215, 0, 150),
+                );
+            }
+            let driver = match record.is_human() {
+                true => "human",
+                false => "network",
+            };
+            let row = [
+                format!("{}", i + 1),
+                format!("{:.3}s", record.lap_seconds),
+                record.class.clone(),
+                format!("{}", record.run_id),
+                format!("{}", record.generation),
+                format!("{}", record.car_number),
+                format_date(record.timestamp),
+                driver.to_string(),
+            ];
+            for (column, text) in COLUMNS.iter().zip(row.iter()) {
+                draw_text(text, *column, y + ROW_HEIGHT * 0.75, 22.0, BLACK);
+            }
+        }
+
+        self.champion_button.draw();
+        draw_text(&self.message, 520.0, 745.0, 25.0, BLACK);
+    }
+}
@@ -0,0 +1,67 @@
+use crate::config::SimConfig;
+use crate::islands::Archipelago;
+
+// the window runs at about 60 frames a second, so headless runs step the same amount
+const TICK: f32 = 1.0 / 60.0;
+
+// the generations asked for with `--headless <generations>`, None for a windowed run
+pub fn requested_generations(args: &[String]) -> Option<usize> {
+    let i = args.iter().position(|arg| arg == "--headless")?;
+    return match args.get(i + 1) {
+        Some(text) => match text.parse() {
+            Ok(generations) => Some(generations),
+            Err(_) => {
+                println!(
+                    "[headless.rs] - Expected a number of generations, got {}",
+                    text
+                );
+                None
+            }
+        },
+        None => Some(1),
+    };
+}
+
+// trains with the default settings and no window until every island has finished the
+// generations, each generation still writes its run folder and report like a windowed run
+pub fn run(generations: usize) {
+    let config = SimConfig {
+        seed: macroquad::miniquad::date::now() as u64,
+        headless: true,
+        ..SimConfig::default()
+    };
+    let Some(mut islands) = Archipelago::new(config) else {
+        println!("[headless.rs] - Couldn't start the headless run");
+        return;
+    };
+    println!("HEADLESS - training for {} generations", generations);
+    while islands.get_generation() < generations {
+        islands.step(TICK);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(list: &[&str]) -> Vec<String> {
+        return list.iter().map(|s| s.to_string()).collect();
+    }
+
+    #[test]
+    fn reads_the_generation_count() {
+        assert_eq!(
+            requested_generations(&args(&["racers", "--headless", "5"])),
+            Some(5)
+        );
+        assert_eq!(
+            requested_generations(&args(&["racers", "--headless"])),
+            Some(1)
+        );
+        assert_eq!(requested_generations(&args(&["racers"])), None);
+        assert_eq!(
+            requested_generations(&args(&["racers", "--headless", "x"])),
+            None
+        );
+    }
+}
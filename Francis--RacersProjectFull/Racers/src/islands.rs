@@ -0,0 +1,175 @@
+use crate::camera::Camera;
+use crate::car::Car;
+use crate::config::SimConfig;
+use crate::population::{Population, CHAMPION_PATH};
+use crate::track::Track;
+use macroquad::prelude::*;
+
+// how each island breeds, (mutation scale, parent pool)
+// the first is the usual top 2 breeding so one island trains as before
+const ISLAND_SETTINGS: [(f32, usize); 4] = [(1.0, 2), (0.5, 4), (2.0, 2), (1.0, 8)];
+
+const SHOWN_COLOUR: Color = color_u8!(0, 90, 200, 255);
+
+// populations that train apart and swap their best genomes every few generations,
+// which keeps top 2 breeding from settling on one idea too early
+// islands are joined in a ring, each sends migrants on to the next
+// only one island is drawn at a time, Tab switches between them
+// every island races on the config's track so the view stays put when switching
+pub struct Archipelago {
+    islands: Vec<Population>,
+    shown: usize,
+    last_generations: Vec<usize>, // to notice when an island finishes a generation
+    migration_interval: usize,
+    migrants: usize,
+}
+
+impl Archipelago {
+    // None if the islands can't be made, e.g. the track doesn't exist
+    pub fn new(config: SimConfig) -> Option<Self> {
+        let mut islands = vec![];
+        for i in 0..config.islands.max(1) {
+            let mut island_config = config.clone();
+            // each island gets its own seed and so its own run folder
+            island_config.seed = config.seed + i as u64;
+            let (mutation_scale, parent_pool) = ISLAND_SETTINGS[i % ISLAND_SETTINGS.len()];
+            island_config.mutation_scale *= mutation_scale;
+            island_config.parent_pool = island_config.parent_pool.max(parent_pool);
+            islands.push(Population::new(island_config)?);
+        }
+
+        return Some(Self {
+            last_generations: vec![0; islands.len()],
+            islands,
+            shown: 0,
+            migration_interval: config.migration_interval.max(1),
+            migrants: config.migrants,
+        });
+    }
+
+    pub fn get_track(&self) -> &Track {
+        return self.islands[self.shown].get_track();
+    }
+
+    pub fn get_best_car(&self) -> Option<&Car> {
+        return self.islands[self.shown].get_best_car();
+    }
+
+    pub fn update_selection(&mut self, camera: &Camera) {
+        self.islands[self.shown].update_selection(camera);
+    }
+
+    pub fn update(&mut self) {
+        if is_key_pressed(KeyCode::Tab) {
+            self.shown = (self.shown + 1) % self.islands.len();
+        }
+
+        for island in self.islands.iter_mut() {
+            island.update();
+        }
+        self.finish_generations();
+    }
+
+    // a tick of every island without the keys, for runs without a window
+    pub fn step(&mut self, dt: f32) {
+        for island in self.islands.iter_mut() {
+            island.step(dt);
+        }
+        self.finish_generations();
+    }
+
+    // the generation every island has got to
+    pub fn get_generation(&self) -> usize {
+        return self
+            .islands
+            .iter()
+            .map(|island| island.get_generation())
+            .min()
+            .unwrap_or(0);
+    }
+
+    fn finish_generations(&mut self) {
+        for i in 0..self.islands.len() {
+            let generation = self.islands[i].get_generation();
+            if generation == self.last_generations[i] {
+                continue;
+            }
+            self.last_generations[i] = generation;
+            self.save_champion(i);
+            if self.islands.len() > 1 && generation.is_multiple_of(self.migration_interval) {
+                self.migrate(i);
+            }
+        }
+    }
+
+    // every island shares champion.net, so an island that has just finished a generation
+    // only writes its champion if no other island's latest one is fitter
+    fn save_champion(&self, i: usize) {
+        let Some((brain, fitness)) = self.islands[i].get_champion() else {
+            return;
+        };
+        let beaten = self
+            .islands
+            .iter()
+            .filter_map(|island| island.get_champion())
+            .any(|(_, other)| other > fitness);
+        if !beaten {
+            brain.save(CHAMPION_PATH);
+        }
+    }
+
+    // an island that has just finished every k-th generation sends its best genomes on
+    // they join the next island when its current generation ends
+    fn migrate(&mut self, i: usize) {
+        let migrants = self.islands[i].get_migrants(self.migrants);
+        let next = (i + 1) % self.islands.len();
+        println!(
+            "MIGRATION - {} genomes from island {} to island {}",
+            migrants.len(),
+            i + 1,
+            next + 1
+        );
+        self.islands[next].receive_migrants(migrants, i);
+    }
+
+    pub fn draw_world(&self) {
+        self.islands[self.shown].draw_world();
+    }
+
+    pub fn draw_hud(&self) {
+        self.islands[self.shown].draw_hud();
+        if self.islands.len() < 2 {
+            return;
+        }
+
+        // a line per island above the other hud text
+        for (i, island) in self.islands.iter().enumerate() {
+            let best = match island.get_last_stats() {
+                Some(stats) => format!("best {}", stats.best),
+                None => "no results yet".to_string(),
+            };
+            let next_migration =
+                self.migration_interval - island.get_generation() % self.migration_interval;
+            let text = format!(
+                "Island {} - gen {}, {}, migrates in {}",
+                i + 1,
+                island.get_generation(),
+                best,
+                next_migration
+            );
+            let colour = match i == self.shown {
+                true => SHOWN_COLOUR,
+                false => BLACK,
+            };
+            let y = screen_height() - 170.0 - 25.0 * (self.islands.len() - 1 - i) as f32;
+            draw_text(&text, 20.0, y, 25.0, colour);
+        }
+        draw_text(
+            "Tab shows the next island",
+            20.0,
+            screen_height() - 170.0 - 25.0 * self.islands.len() as f32,
+            20.0,
+            DARKGRAY,
+        );
+    }
+}
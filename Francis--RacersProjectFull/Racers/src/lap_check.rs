@@ -0,0 +1,243 @@
+use crate::geometry::segment_intersection;
+use crate::track::Track;
+use macroquad::prelude::*;
+use std::fmt;
+
+// why a lap didn't count
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum LapInvalid {
+    MissedCheckpoint(usize),
+    TrackLimits(usize), // number of times the car hit the walls
+}
+
+// follows a car over the checkpoint lines to decide if its lap counts
+#[derive(Clone)]
+pub struct LapCheck {
+    next_checkpoint: usize,     // the checkpoint line the car should cross next
+    checkpoints_crossed: usize, // in order since the lap started
+    missed_checkpoint: Option<usize>,
+    wall_hits: usize,
+    last_pos: Vec2, // where the car was when the lines were last checked
+}
+
+impl fmt::Display for LapInvalid {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            LapInvalid::MissedCheckpoint(i) => write!(f, "missed checkpoint {}", i),
+            LapInvalid::TrackLimits(hits) => write!(f, "track limits ({} wall hits)", hits),
+        }
+    }
+}
+
+impl LapCheck {
+    pub fn new(start_pos: Vec2) -> Self {
+        // cars start just past the finish line, which is checkpoint 0
+        Self {
+            next_checkpoint: 1,
+            checkpoints_crossed: 0,
+            missed_checkpoint: None,
+            wall_hits: 0,
+            last_pos: start_pos,
+        }
+    }
+
+    pub fn add_wall_hit(&mut self) {
+        self.wall_hits += 1;
+    }
+
+    pub fn cross_checkpoints(&mut self, track: &Track, pos: Vec2) {
+        // every checkpoint line the car went over since the last check
+        let from = self.last_pos;
+        self.last_pos = pos;
+        if from == pos {
+            return;
+        }
+
+        let n = track.checkpoint_count();
+        // how far round the lap a checkpoint is, the finish line comes last
+        let lap_order = |i: usize| (i + n - 1) % n;
+
+        for i in 0..n {
+            let (start, end) = track.get_checkpoint(i);
+            if segment_intersection(from, pos, start, end).is_none() {
+                continue;
+            }
+
+            if (pos - from).dot(track.get_checkpoint_direction(i)) > 0.0 {
+                if lap_order(i) >= lap_order(self.next_checkpoint) {
+                    // anything between the expected line and this one was skipped
+                    if i != self.next_checkpoint && self.missed_checkpoint.is_none() {
+                        self.missed_checkpoint = Some(self.next_checkpoint);
+                    }
+                    self.checkpoints_crossed += 1;
+                    self.next_checkpoint = (i + 1) % n;
+                }
+            } else if i == (self.next_checkpoint + n - 1) % n && self.checkpoints_crossed > 0 {
+                // reversed back over the last line so it has to be crossed again
+                self.checkpoints_crossed -= 1;
+                self.next_checkpoint = i;
+            }
+        }
+    }
+
+    // judges the lap that just finished and starts checking the next one
+    pub fn finish_lap(&mut self, track: &Track) -> Option<LapInvalid> {
+        let result = self.judge(track);
+
+        // the next line is 1, unless the sector wrapped a tick before the car
+        // reached the finish line and it's still to be crossed
+        if self.next_checkpoint != 0 {
+            self.next_checkpoint = 1;
+        }
+        self.checkpoints_crossed = 0;
+        self.missed_checkpoint = None;
+        self.wall_hits = 0;
+        return result;
+    }
+
+    fn judge(&self, track: &Track) -> Option<LapInvalid> {
+        // every line apart from the finish has to have been crossed in order
+        if let Some(missed) = self.missed_checkpoint {
+            return Some(LapInvalid::MissedCheckpoint(missed));
+        }
+        if self.checkpoints_crossed < track.checkpoint_count() - 1 {
+            return Some(LapInvalid::MissedCheckpoint(self.next_checkpoint));
+        }
+        if self.wall_hits > 0 {
+            return Some(LapInvalid::TrackLimits(self.wall_hits));
+        }
+        return None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // a 400 wide square driven clockwise on screen, checkpoint 0 is the finish line
+    const SIDE: f32 = 400.0;
+    const LAP: f32 = SIDE * 4.0;
+    // small enough that no step jumps a whole corner
+    const STEP: f32 = 7.0;
+
+    fn square_track() -> Track {
+        let points = [
+            vec2(0.0, 0.0),
+            vec2(SIDE, 0.0),
+            vec2(SIDE, SIDE),
+            vec2(0.0, SIDE),
+        ];
+        return Track::new("square", &points, 100.0);
+    }
+
+    // the point on the centre line a distance round the lap from the finish line
+    fn point_at(distance: f32) -> Vec2 {
+        let d = distance.rem_euclid(LAP);
+        let side = (d / SIDE) as usize;
+        let along = d % SIDE;
+        return match side {
+            0 => vec2(along, 0.0),
+            1 => vec2(SIDE, along),
+            2 => vec2(SIDE - along, SIDE),
+            _ => vec2(0.0, SIDE - along),
+        };
+    }
+
+    // moves the car in small steps along the centre line, either way round
+    fn drive(check: &mut LapCheck, track: &Track, from: f32, to: f32) {
+        let direction = (to - from).signum();
+        let mut distance = from;
+        while (to - distance) * direction > 0.0 {
+            distance = (distance + STEP * direction).clamp(from.min(to), from.max(to));
+            check.cross_checkpoints(track, point_at(distance));
+        }
+    }
+
+    fn start() -> (Track, LapCheck, f32) {
+        let track = square_track();
+        // a little off the middle so the steps never land on a corner
+        let start = SIDE / 2.0 + 1.0;
+        let check = LapCheck::new(point_at(start));
+        return (track, check, start);
+    }
+
+    #[test]
+    fn clean_lap_counts() {
+        let (track, mut check, start) = start();
+        drive(&mut check, &track, start, LAP + 10.0);
+        assert_eq!(check.finish_lap(&track), None);
+    }
+
+    #[test]
+    fn stopping_short_misses_checkpoints() {
+        let (track, mut check, start) = start();
+        drive(&mut check, &track, start, SIDE * 1.5);
+        assert_eq!(
+            check.finish_lap(&track),
+            Some(LapInvalid::MissedCheckpoint(2))
+        );
+    }
+
+    #[test]
+    fn cutting_a_corner_misses_its_checkpoint() {
+        let (track, mut check, start) = start();
+        drive(&mut check, &track, start, SIDE * 1.6);
+        // straight across the inside of the corner at checkpoint 2
+        check.cross_checkpoints(&track, point_at(SIDE * 2.4));
+        drive(&mut check, &track, SIDE * 2.4, LAP + 10.0);
+        assert_eq!(
+            check.finish_lap(&track),
+            Some(LapInvalid::MissedCheckpoint(2))
+        );
+    }
+
+    #[test]
+    fn reversing_over_a_line_means_crossing_it_again() {
+        let (track, mut check, start) = start();
+        drive(&mut check, &track, start, SIDE + 50.0);
+        assert_eq!(check.next_checkpoint, 2);
+        drive(&mut check, &track, SIDE + 50.0, SIDE - 50.0);
+        assert_eq!(check.next_checkpoint, 1);
+        assert_eq!(check.checkpoints_crossed, 0);
+        drive(&mut check, &track, SIDE - 50.0, LAP + 10.0);
+        assert_eq!(check.finish_lap(&track), None);
+    }
+
+    #[test]
+    fn reversing_without_crossing_again_misses_it() {
+        let (track, mut check, start) = start();
+        drive(&mut check, &track, start, SIDE * 2.0 + 50.0);
+        drive(&mut check, &track, SIDE * 2.0 + 50.0, SIDE * 2.0 - 50.0);
+        // the lap is ended early, still short of checkpoint 2
+        assert_eq!(
+            check.finish_lap(&track),
+            Some(LapInvalid::MissedCheckpoint(2))
+        );
+    }
+
+    #[test]
+    fn late_finish_line_crossing_keeps_the_next_lap_valid() {
+        let (track, mut check, start) = start();
+        // the sector wraps just before the car reaches the finish line
+        drive(&mut check, &track, start, LAP - 5.0);
+        assert_eq!(check.finish_lap(&track), None);
+        assert_eq!(check.next_checkpoint, 0);
+
+        // then it crosses the line and drives another full lap
+        drive(&mut check, &track, LAP - 5.0, LAP * 2.0 + 10.0);
+        assert_eq!(check.finish_lap(&track), None);
+        assert_eq!(check.next_checkpoint, 1);
+    }
+
+    #[test]
+    fn wall_hits_invalidate_a_complete_lap() {
+        let (track, mut check, start) = start();
+        drive(&mut check, &track, start, SIDE * 2.0);
+        check.add_wall_hit();
+        drive(&mut check, &track, SIDE * 2.0, LAP + 10.0);
+        assert_eq!(check.finish_lap(&track), Some(LapInvalid::TrackLimits(1)));
+        // the count starts again with the next lap
+        drive(&mut check, &track, LAP + 10.0, LAP * 2.0 + 10.0);
+        assert_eq!(check.finish_lap(&track), None);
+    }
+}
@@ -0,0 +1,451 @@
+use crate::utils::repeat_space;
+use macroquad::prelude::*;
+use std::collections::HashMap;
+
+pub const DEFAULT_CAPACITY: usize = 10;
+
+// how long a new entry stays highlighted on the leaderboard
+const HIGHLIGHT_SECONDS: f64 = 3.0;
+const PANEL_WIDTH: f32 = 520.0;
+const ROW_HEIGHT: f32 = 24.0;
+const SECTOR_STRIP_X: f32 = 330.0; // where the coloured sector blocks start in each row
+const SECTOR_STRIP_WIDTH: f32 = 180.0;
+
+// timing screen colours
+const PURPLE_SECTOR: Color = color_u8!(160, 50, 220, 255);
+const GREEN_SECTOR: Color = color_u8!(40, 190, 60, 255);
+const YELLOW_SECTOR: Color = color_u8!(235, 200, 30, 255);
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct LapEntry {
+    pub car_number: usize,
+    pub generation: usize,
+    pub lap: usize, // which of the car's laps it was, counting from 1
+    pub lap_seconds: f32,
+    pub sectors: Vec<f32>, // split times in seconds, empty if they weren't measured
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum SectorColour {
+    Purple, // fastest anyone has driven the sector
+    Green,  // the car's own fastest
+    Yellow, // neither
+}
+
+// the fastest laps so far, fastest first
+pub struct Leaderboard {
+    slots: Vec<Option<LapEntry>>,
+    best_sectors: Vec<f32>, // fastest of each sector from every lap entered, even ones that didn't place
+    personal_best_sectors: HashMap<(usize, usize), Vec<f32>>, // by (car number, generation)
+    newest: Option<(LapEntry, f64)>, // the last lap to make the board and when it arrived
+    invalid_laps: usize,
+    last_invalid: Option<(LapEntry, String)>, // kept off the board, with the reason why
+    lap_column: bool, // show each car's lap number instead of its generation
+}
+
+impl LapEntry {
+    pub fn new(car_number: usize, generation: usize, lap_seconds: f32, sectors: Vec<f32>) -> Self {
+        Self {
+            car_number,
+            generation,
+            lap: 0,
+            lap_seconds,
+            sectors,
+        }
+    }
+}
+
+impl Default for Leaderboard {
+    fn default() -> Self {
+        Self::new(DEFAULT_CAPACITY)
+    }
+}
+
+impl Leaderboard {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            slots: vec![None; capacity],
+            best_sectors: vec![],
+            personal_best_sectors: HashMap::new(),
+            newest: None,
+            invalid_laps: 0,
+            last_invalid: None,
+            lap_column: false,
+        }
+    }
+
+    // for drive mode where every lap is in the same session, the generation would
+    // always be the same
+    pub fn show_lap_numbers(mut self) -> Self {
+        self.lap_column = true;
+        return self;
+    }
+
+    pub fn capacity(&self) -> usize {
+        return self.slots.len();
+    }
+
+    pub fn get_slots(&self) -> &[Option<LapEntry>] {
+        return &self.slots;
+    }
+
+    // the filled slots in order
+    pub fn entries(&self) -> impl Iterator<Item = &LapEntry> {
+        return self.slots.iter().map_while(|slot| slot.as_ref());
+    }
+
+    pub fn get_fastest(&self) -> Option<&LapEntry> {
+        return self.slots.first()?.as_ref();
+    }
+
+    pub fn get_slowest(&self) -> Option<&LapEntry> {
+        return self.entries().last();
+    }
+
+    // returns the place the lap took, None if it wasn't fast enough
+    pub fn enter(&mut self, entry: LapEntry) -> Option<usize> {
+        self.update_sector_bests(&entry);
+
+        let place = self.slots.iter().position(|slot| match slot {
+            None => true,
+            Some(other) => entry.lap_seconds < other.lap_seconds,
+        })?;
+
+        // shift all times down 1 after this, the last one drops off
+        self.slots[place..].rotate_right(1);
+        self.newest = Some((entry.clone(), miniquad::date::now()));
+        self.slots[place] = Some(entry);
+        return Some(place);
+    }
+
+    // invalid laps are counted but never placed or used for the sector bests
+    pub fn submit(&mut self, entry: LapEntry, invalid_reason: Option<String>) -> Option<usize> {
+        match invalid_reason {
+            Some(reason) => {
+                self.invalid_laps += 1;
+                self.last_invalid = Some((entry, reason));
+                return None;
+            }
+            None => return self.enter(entry),
+        }
+    }
+
+    fn update_sector_bests(&mut self, entry: &LapEntry) {
+        if entry.sectors.is_empty() {
+            return;
+        }
+        // a track with a different number of sectors starts the bests again
+        if self.best_sectors.len() != entry.sectors.len() {
+            self.best_sectors = entry.sectors.clone();
+        }
+        merge_fastest(&mut self.best_sectors, &entry.sectors);
+
+        let personal = self
+            .personal_best_sectors
+            .entry((entry.car_number, entry.generation))
+            .or_insert_with(|| entry.sectors.clone());
+        if personal.len() != entry.sectors.len() {
+            *personal = entry.sectors.clone();
+        }
+        merge_fastest(personal, &entry.sectors);
+    }
+
+    pub fn get_best_sectors(&self) -> &[f32] {
+        return &self.best_sectors;
+    }
+
+    // the lap made of the fastest version of every sector
+    pub fn theoretical_best(&self) -> Option<f32> {
+        if self.best_sectors.is_empty() {
+            return None;
+        }
+        return Some(self.best_sectors.iter().sum());
+    }
+
+    pub fn sector_colour(&self, entry: &LapEntry, sector: usize) -> SectorColour {
+        let time = entry.sectors[sector];
+        if self
+            .best_sectors
+            .get(sector)
+            .is_some_and(|best| time <= *best)
+        {
+            return SectorColour::Purple;
+        }
+        let personal = self
+            .personal_best_sectors
+            .get(&(entry.car_number, entry.generation));
+        if personal
+            .and_then(|p| p.get(sector))
+            .is_some_and(|best| time <= *best)
+        {
+            return SectorColour::Green;
+        }
+        return SectorColour::Yellow;
+    }
+
+    pub fn print_times(&self) {
+        // each column will be 10 spaces width
+        println!("__________________________________");
+        println!("|Car no.   |Generation|Lap Time  |");
+        println!("__________________________________");
+
+        for t in self.entries() {
+            print!("|{}", t.car_number);
+            repeat_space(10 - format!("{}", t.car_number).len());
+            print!("|{}", t.generation);
+            repeat_space(10 - format!("{}", t.generation).len());
+            print!("|{:.3}", t.lap_seconds);
+            repeat_space(10 - format!("{:.3}", t.lap_seconds).len());
+            println!("|");
+        }
+
+        println!("__________________________________");
+    }
+
+    pub fn draw(&self, x: f32, y: f32) {
+        // draws the board as a panel in screen pixels
+        let filled = self.entries().count();
+        let height = ROW_HEIGHT * (filled as f32 + 4.0);
+        draw_rectangle(x, y, PANEL_WIDTH, height, color_u8!(255, 255, 255, 180));
+        draw_rectangle_lines(x, y, PANEL_WIDTH, height, 2.0, BLACK);
+
+        let columns = [10.0, 45.0, 105.0, 165.0, 250.0, SECTOR_STRIP_X];
+        let third = match self.lap_column {
+            true => "Lap",
+            false => "Gen",
+        };
+        let header = ["#", "Car", third, "Time", "Delta", "Sectors"];
+        for (column, text) in columns.iter().zip(header.iter()) {
+            draw_text(text, x + column, y + ROW_HEIGHT, 22.0, BLACK);
+        }
+        self.draw_invalid(x, y + height - 8.0);
+        if filled == 0 {
+            draw_text(
+                "No laps yet",
+                x + 10.0,
+                y + ROW_HEIGHT * 2.0,
+                22.0,
+                DARKGRAY,
+            );
+            return;
+        }
+
+        let best = self.slots[0].as_ref().map_or(0.0, |e| e.lap_seconds);
+        let now = miniquad::date::now();
+        for (i, entry) in self.entries().enumerate() {
+            let row_y = y + ROW_HEIGHT * (i as f32 + 2.0);
+
+            // fade the highlight out on the newest entry
+            if let Some((newest, arrived)) = &self.newest {
+                let age = now - arrived;
+                if newest == entry && age < HIGHLIGHT_SECONDS {
+                    let alpha = 1.0 - (age / HIGHLIGHT_SECONDS) as f32;
+                    let colour = Color::new(1.0, 0.85, 0.0, alpha * 0.8);
+                    draw_rectangle(
+                        x + 2.0,
+                        row_y - ROW_HEIGHT + 6.0,
+                        PANEL_WIDTH - 4.0,
+                        ROW_HEIGHT,
+                        colour,
+                    );
+                }
+            }
+
+            let delta = match i {
+                0 => "-".to_string(),
+                _ => format!("+{:.3}", entry.lap_seconds - best),
+            };
+            let row = [
+                format!("{}", i + 1),
+                format!("{}", entry.car_number),
+                match self.lap_column {
+                    true => format!("{}", entry.lap),
+                    false => format!("{}", entry.generation),
+                },
+                format!("{:.3}s", entry.lap_seconds),
+                delta,
+            ];
+            for (column, text) in columns.iter().zip(row.iter()) {
+                draw_text(text, x + column, row_y, 22.0, BLACK);
+            }
+
+            // one block per sector coloured like a timing screen
+            let block = SECTOR_STRIP_WIDTH / entry.sectors.len().max(1) as f32;
+            for sector in 0..entry.sectors.len() {
+                let colour = match self.sector_colour(entry, sector) {
+                    SectorColour::Purple => PURPLE_SECTOR,
+                    SectorColour::Green => GREEN_SECTOR,
+                    SectorColour::Yellow => YELLOW_SECTOR,
+                };
+                let block_x = x + SECTOR_STRIP_X + block * sector as f32;
+                draw_rectangle(block_x, row_y - 14.0, block - 1.0, 14.0, colour);
+            }
+        }
+
+        if let Some(theoretical) = self.theoretical_best() {
+            let text = format!("Theoretical best: {:.3}s", theoretical);
+            draw_text(
+                &text,
+                x + 10.0,
+                y + ROW_HEIGHT * (filled as f32 + 2.0),
+                22.0,
+                BLACK,
+            );
+        }
+    }
+}
+
+impl Leaderboard {
+    fn draw_invalid(&self, x: f32, y: f32) {
+        let Some((entry, reason)) = &self.last_invalid else {
+            return;
+        };
+        let text = format!(
+            "{} invalid, last: car {} {}",
+            self.invalid_laps, entry.car_number, reason
+        );
+        draw_text(&text, x + 10.0, y, 20.0, RED);
+    }
+}
+
+// keeps the faster of each pair of splits
+fn merge_fastest(best: &mut [f32], sectors: &[f32]) {
+    for (best, time) in best.iter_mut().zip(sectors.iter()) {
+        *best = best.min(*time);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lap(car_number: usize, lap_seconds: f32) -> LapEntry {
+        LapEntry::new(car_number, 0, lap_seconds, vec![])
+    }
+
+    fn times(board: &Leaderboard) -> Vec<f32> {
+        return board.entries().map(|e| e.lap_seconds).collect();
+    }
+
+    #[test]
+    fn first_lap_goes_top_of_an_empty_board() {
+        let mut board = Leaderboard::new(3);
+        assert_eq!(board.enter(lap(1, 20.0)), Some(0));
+        assert_eq!(times(&board), vec![20.0]);
+        assert_eq!(board.get_fastest().unwrap().car_number, 1);
+    }
+
+    #[test]
+    fn laps_are_placed_in_order() {
+        let mut board = Leaderboard::new(3);
+        board.enter(lap(1, 20.0));
+        assert_eq!(board.enter(lap(2, 25.0)), Some(1));
+        assert_eq!(board.enter(lap(3, 18.0)), Some(0));
+        assert_eq!(times(&board), vec![18.0, 20.0, 25.0]);
+    }
+
+    #[test]
+    fn full_board_drops_the_slowest() {
+        let mut board = Leaderboard::new(3);
+        for (car, time) in [(1, 20.0), (2, 21.0), (3, 22.0)] {
+            board.enter(lap(car, time));
+        }
+        assert_eq!(board.enter(lap(4, 23.0)), None);
+        assert_eq!(board.enter(lap(5, 20.5)), Some(1));
+        assert_eq!(times(&board), vec![20.0, 20.5, 21.0]);
+        assert_eq!(board.get_slowest().unwrap().car_number, 2);
+    }
+
+    #[test]
+    fn zero_capacity_places_nothing() {
+        let mut board = Leaderboard::new(0);
+        assert_eq!(board.enter(lap(1, 10.0)), None);
+        assert!(board.get_fastest().is_none());
+        assert_eq!(board.entries().count(), 0);
+    }
+
+    #[test]
+    fn a_tie_goes_behind_the_lap_already_there() {
+        let mut board = Leaderboard::new(3);
+        board.enter(lap(1, 20.0));
+        assert_eq!(board.enter(lap(2, 20.0)), Some(1));
+        assert_eq!(board.get_fastest().unwrap().car_number, 1);
+
+        // a tie with the last place of a full board doesn't get on
+        board.enter(lap(3, 21.0));
+        assert_eq!(board.enter(lap(4, 21.0)), None);
+    }
+
+    #[test]
+    fn invalid_laps_are_never_placed() {
+        let mut board = Leaderboard::new(3);
+        let place = board.submit(lap(1, 5.0), Some("cut".to_string()));
+        assert_eq!(place, None);
+        assert!(board.get_fastest().is_none());
+        assert!(board.theoretical_best().is_none());
+    }
+
+    #[test]
+    fn merge_keeps_the_faster_split() {
+        let mut best = vec![10.0, 5.0, 7.0];
+        merge_fastest(&mut best, &[9.0, 6.0, 7.0]);
+        assert_eq!(best, vec![9.0, 5.0, 7.0]);
+    }
+
+    #[test]
+    fn theoretical_best_adds_the_fastest_sectors() {
+        let mut board = Leaderboard::new(3);
+        assert_eq!(board.theoretical_best(), None);
+        board.enter(LapEntry::new(1, 0, 30.0, vec![10.0, 12.0, 8.0]));
+        board.enter(LapEntry::new(2, 0, 31.0, vec![11.0, 10.0, 10.0]));
+        assert_eq!(board.get_best_sectors(), &[10.0, 10.0, 8.0]);
+        assert_eq!(board.theoretical_best(), Some(28.0));
+    }
+
+    #[test]
+    fn sectors_are_coloured_like_a_timing_screen() {
+        let mut board = Leaderboard::new(5);
+        let first = LapEntry::new(1, 0, 30.0, vec![10.0, 10.0, 10.0]);
+        let second = LapEntry::new(1, 0, 29.0, vec![9.0, 10.5, 9.5]);
+        let other = LapEntry::new(2, 0, 31.0, vec![9.5, 11.0, 9.0]);
+        board.enter(first.clone());
+        board.enter(second.clone());
+        board.enter(other.clone());
+
+        // fastest of anyone
+        assert_eq!(board.sector_colour(&second, 0), SectorColour::Purple);
+        assert_eq!(board.sector_colour(&other, 2), SectorColour::Purple);
+        assert_eq!(board.sector_colour(&first, 1), SectorColour::Purple);
+        // the car's own best but beaten by someone else
+        assert_eq!(board.sector_colour(&second, 2), SectorColour::Green);
+        // neither
+        assert_eq!(board.sector_colour(&second, 1), SectorColour::Yellow);
+        assert_eq!(board.sector_colour(&first, 0), SectorColour::Yellow);
+        assert_eq!(board.sector_colour(&other, 1), SectorColour::Green);
+    }
+
+    #[test]
+    fn personal_bests_are_kept_per_car_and_generation() {
+        let mut board = Leaderboard::new(5);
+        board.enter(LapEntry::new(1, 0, 20.0, vec![10.0, 10.0]));
+        board.enter(LapEntry::new(2, 0, 18.0, vec![9.0, 9.0]));
+        // the same car number in a later generation is a different car
+        let later = LapEntry::new(1, 1, 21.0, vec![10.5, 10.5]);
+        board.enter(later.clone());
+        assert_eq!(board.sector_colour(&later, 0), SectorColour::Green);
+    }
+
+    #[test]
+    fn drive_mode_board_keeps_lap_numbers() {
+        let mut board = Leaderboard::default().show_lap_numbers();
+        assert!(board.lap_column && !Leaderboard::default().lap_column);
+        for (number, seconds) in [(3, 30.0), (4, 28.0)] {
+            board.enter(LapEntry {
+                lap: number,
+                ..lap(1, seconds)
+            });
+        }
+        let laps: Vec<usize> = board.entries().map(|e| e.lap).collect();
+        assert_eq!(laps, [4, 3]);
+    }
+}
@@ -0,0 +1,418 @@
+use macroquad::prelude::*;
+use std::collections::HashSet;
+use std::fs;
+use std::fs::OpenOptions;
+use std::io::prelude::*;
+
+const LINEAGE_NAME: &str = "lineage.jsonl";
+const ANCESTRY_NAME: &str = "ancestry.dot";
+const PANEL_WIDTH: f32 = 700.0;
+const ROW_HEIGHT: f32 = 26.0;
+const ROWS_SHOWN: usize = 16;
+
+// unique across the whole run, unlike car numbers which are reused every generation
+pub type GenomeId = u64;
+
+// where one network came from and how it did
+pub struct GenomeRecord {
+    pub id: GenomeId,
+    pub generation: usize,
+    pub car_number: usize,
+    pub parents: Vec<GenomeId>, // empty for the random first generation
+    // per layer, the last weight and bias index copied from the second parent
+    pub crossovers: Vec<(usize, usize)>,
+    pub total_mutations: usize,   // genes replaced with a new random value
+    pub partial_mutations: usize, // genes nudged from the value they had
+    pub migrated_from: Option<usize>, // the island it came from, its parents are over there
+    pub fitness: Option<i32>,     // filled in when its generation ends
+    pub best_lap: Option<f32>,
+}
+
+// the champion's family tree, worked out once when its generation ends
+// rather than on every frame the panel is open
+struct ChampionTree {
+    id: GenomeId,
+    ancestors: Vec<GenomeId>, // newest generation first
+    improvements: HashSet<GenomeId>,
+}
+
+// the family tree of a run
+// ids are handed out in order so a genome's id is also its index
+pub struct Lineage {
+    records: Vec<GenomeRecord>,
+    champion: Option<ChampionTree>, // best of the last finished generation
+    pub visible: bool,
+}
+
+impl Default for Lineage {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl GenomeRecord {
+    pub fn new(generation: usize, car_number: usize) -> Self {
+        Self {
+            id: 0,
+            generation,
+            car_number,
+            parents: vec![],
+            crossovers: vec![],
+            total_mutations: 0,
+            partial_mutations: 0,
+            migrated_from: None,
+            fitness: None,
+            best_lap: None,
+        }
+    }
+
+    pub fn to_json(&self) -> String {
+        let parents: Vec<String> = self.parents.iter().map(|p| p.to_string()).collect();
+        let crossovers: Vec<String> = self
+            .crossovers
+            .iter()
+            .map(|(w, b)| format!("[{},{}]", w, b))
+            .collect();
+        return format!(
+            "{{\"id\":{},\"generation\":{},\"car\":{},\"parents\":[{}],\"crossovers\":[{}],\"total_mutations\":{},\"partial_mutations\":{},\"migrated_from\":{},\"fitness\":{},\"best_lap\":{}}}",
+            self.id,
+            self.generation,
+            self.car_number,
+            parents.join(","),
+            crossovers.join(","),
+            self.total_mutations,
+            self.partial_mutations,
+            json_option(self.migrated_from),
+            json_option(self.fitness),
+            json_option(self.best_lap.map(|t| format!("{:.3}", t)))
+        );
+    }
+}
+
+fn json_option<T: ToString>(value: Option<T>) -> String {
+    return value.map_or("null".to_string(), |v| v.to_string());
+}
+
+impl Lineage {
+    pub fn new() -> Self {
+        Self {
+            records: vec![],
+            champion: None,
+            visible: false,
+        }
+    }
+
+    pub fn next_id(&self) -> GenomeId {
+        return self.records.len() as GenomeId;
+    }
+
+    // gives the record the next id and returns it
+    pub fn add(&mut self, mut record: GenomeRecord) -> GenomeId {
+        record.id = self.next_id();
+        self.records.push(record);
+        return self.records.len() as GenomeId - 1;
+    }
+
+    pub fn get(&self, id: GenomeId) -> Option<&GenomeRecord> {
+        return self.records.get(id as usize);
+    }
+
+    pub fn set_result(&mut self, id: GenomeId, fitness: i32, best_lap: Option<f32>) {
+        if let Some(record) = self.records.get_mut(id as usize) {
+            record.fitness = Some(fitness);
+            record.best_lap = best_lap;
+        }
+    }
+
+    // the genome and everything it descends from, newest generation first
+    pub fn ancestry(&self, id: GenomeId) -> Vec<&GenomeRecord> {
+        let mut found: Vec<GenomeId> = vec![id];
+        let mut seen: HashSet<GenomeId> = HashSet::from([id]);
+        let mut i = 0;
+        while i < found.len() {
+            if let Some(record) = self.get(found[i]) {
+                for parent in record.parents.iter() {
+                    if seen.insert(*parent) {
+                        found.push(*parent);
+                    }
+                }
+            }
+            i += 1;
+        }
+        let mut ancestors: Vec<&GenomeRecord> =
+            found.iter().filter_map(|id| self.get(*id)).collect();
+        ancestors.sort_by(|a, b| b.generation.cmp(&a.generation).then(a.id.cmp(&b.id)));
+        return ancestors;
+    }
+
+    // the ancestors that beat the fitness of every ancestor before them, by generation
+    pub fn improvements(&self, id: GenomeId) -> HashSet<GenomeId> {
+        // oldest first, fittest first within a generation
+        let mut ancestors = self.ancestry(id);
+        ancestors.sort_by(|a, b| {
+            a.generation
+                .cmp(&b.generation)
+                .then(b.fitness.cmp(&a.fitness))
+        });
+        let mut best = i32::MIN;
+        let mut improvements = HashSet::new();
+        for record in ancestors {
+            if let Some(fitness) = record.fitness {
+                if fitness > best {
+                    best = fitness;
+                    improvements.insert(record.id);
+                }
+            }
+        }
+        return improvements;
+    }
+
+    // the best genome of the generation that just ended, after its results are in
+    pub fn set_champion(&mut self, id: GenomeId) {
+        self.champion = Some(ChampionTree {
+            id,
+            ancestors: self.ancestry(id).iter().map(|r| r.id).collect(),
+            improvements: self.improvements(id),
+        });
+    }
+
+    // adds a generation's genomes to the run's log once their fitness is known
+    pub fn save_generation(&self, run_dir: &str, generation: usize) {
+        let path = format!("{}/{}", run_dir, LINEAGE_NAME);
+        let mut text = String::new();
+        for record in self.records.iter().filter(|r| r.generation == generation) {
+            text += &record.to_json();
+            text += "\n";
+        }
+        let result = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&path)
+            .and_then(|mut file| file.write_all(text.as_bytes()));
+        if let Err(e) = result {
+            println!("[lineage.rs] - Couldn't save lineage to {}: {}", path, e);
+        }
+    }
+
+    // the champion's family tree as a graphviz file, key improvements are filled in
+    pub fn save_ancestry(&self, run_dir: &str) {
+        let Some(champion) = &self.champion else {
+            return;
+        };
+        let mut text = "digraph ancestry {\n    rankdir=BT;\n    node [shape=box];\n".to_string();
+        for record in champion.ancestors.iter().filter_map(|id| self.get(*id)) {
+            let style = match champion.improvements.contains(&record.id) {
+                true => ", style=filled, fillcolor=gold",
+                false => "",
+            };
+            text += &format!(
+                "    g{} [label=\"gen {} car {}\\nfitness {}\"{}];\n",
+                record.id,
+                record.generation,
+                record.car_number,
+                json_option(record.fitness),
+                style
+            );
+            for parent in record.parents.iter() {
+                text += &format!("    g{} -> g{};\n", parent, record.id);
+            }
+        }
+        text += "}\n";
+
+        let path = format!("{}/{}", run_dir, ANCESTRY_NAME);
+        if let Err(e) = fs::write(&path, text) {
+            println!("[lineage.rs] - Couldn't save {}: {}", path, e);
+        }
+    }
+
+    pub fn update(&mut self) {
+        if is_key_pressed(KeyCode::L) {
+            self.visible = !self.visible;
+        }
+    }
+
+    // a panel in the middle of the screen listing the champion's ancestors
+    pub fn draw(&self) {
+        if !self.visible {
+            return;
+        }
+        let x = (screen_width() - PANEL_WIDTH) / 2.0;
+        let y = 80.0;
+        let height = ROW_HEIGHT * (ROWS_SHOWN as f32 + 3.0);
+        draw_rectangle(x, y, PANEL_WIDTH, height, color_u8!(255, 255, 255, 230));
+        draw_rectangle_lines(x, y, PANEL_WIDTH, height, 2.0, BLACK);
+
+        let Some(champion) = &self.champion else {
+            draw_text(
+                "Ancestry (L): no champion until a generation ends",
+                x + 10.0,
+                y + ROW_HEIGHT,
+                22.0,
+                BLACK,
+            );
+            return;
+        };
+        let title = format!(
+            "Ancestry (L) of genome {}, gold rows set a new best fitness",
+            champion.id
+        );
+        draw_text(&title, x + 10.0, y + ROW_HEIGHT, 22.0, BLACK);
+
+        let columns = [10.0, 70.0, 150.0, 210.0, 320.0, 430.0, 560.0];
+        let headings = [
+            "Gen",
+            "Genome",
+            "Car",
+            "Fitness",
+            "Best lap",
+            "Parents",
+            "Mutations",
+        ];
+        for (column, heading) in columns.iter().zip(headings.iter()) {
+            draw_text(heading, x + column, y + ROW_HEIGHT * 2.0, 20.0, BLACK);
+        }
+
+        let ancestors = champion.ancestors.iter().filter_map(|id| self.get(*id));
+        for (i, record) in ancestors.take(ROWS_SHOWN).enumerate() {
+            let row_y = y + ROW_HEIGHT * (i as f32 + 3.0);
+            if champion.improvements.contains(&record.id) {
+                draw_rectangle(
+                    x + 2.0,
+                    row_y - ROW_HEIGHT + 6.0,
+                    PANEL_WIDTH - 4.0,
+                    ROW_HEIGHT,
+                    color_u8!(255, 215, 0, 150),
+                );
+            }
+            let parents: Vec<String> = record.parents.iter().map(|p| p.to_string()).collect();
+            let row = [
+                record.generation.to_string(),
+                record.id.to_string(),
+                record.car_number.to_string(),
+                json_option(record.fitness),
+                record
+                    .best_lap
+                    .map_or("-".to_string(), |t| format!("{:.3}s", t)),
+                match record.migrated_from {
+                    Some(island) => format!("island {}", island + 1),
+                    None => parents.join(" x "),
+                },
+                format!("{} + {}", record.total_mutations, record.partial_mutations),
+            ];
+            for (column, text) in columns.iter().zip(row.iter()) {
+                draw_text(text, x + column, row_y, 20.0, BLACK);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn add(
+        lineage: &mut Lineage,
+        generation: usize,
+        parents: &[GenomeId],
+        fitness: i32,
+    ) -> GenomeId {
+        let mut record = GenomeRecord::new(generation, lineage.next_id() as usize + 1);
+        record.parents = parents.to_vec();
+        record.fitness = Some(fitness);
+        return lineage.add(record);
+    }
+
+    fn ids(records: &[&GenomeRecord]) -> Vec<GenomeId> {
+        return records.iter().map(|r| r.id).collect();
+    }
+
+    // generation 0: 0 1 2, generation 1: 3 = 0x1, 4 = 1x2, generation 2: 5 = 3x4
+    fn family() -> Lineage {
+        let mut lineage = Lineage::new();
+        add(&mut lineage, 0, &[], 100);
+        add(&mut lineage, 0, &[], 300);
+        add(&mut lineage, 0, &[], 50);
+        add(&mut lineage, 1, &[0, 1], 250);
+        add(&mut lineage, 1, &[1, 2], 400);
+        add(&mut lineage, 2, &[3, 4], 380);
+        return lineage;
+    }
+
+    #[test]
+    fn ids_are_handed_out_in_order() {
+        let mut lineage = Lineage::new();
+        assert_eq!(add(&mut lineage, 0, &[], 0), 0);
+        assert_eq!(add(&mut lineage, 0, &[], 0), 1);
+        assert_eq!(lineage.get(1).unwrap().id, 1);
+        assert!(lineage.get(2).is_none());
+    }
+
+    #[test]
+    fn shared_ancestors_are_listed_once_newest_first() {
+        let lineage = family();
+        // 1 is a grandparent through both parents
+        assert_eq!(ids(&lineage.ancestry(5)), [5, 3, 4, 0, 1, 2]);
+        assert_eq!(ids(&lineage.ancestry(3)), [3, 0, 1]);
+    }
+
+    #[test]
+    fn first_generation_and_unknown_genomes() {
+        let lineage = family();
+        assert_eq!(ids(&lineage.ancestry(2)), [2]);
+        assert!(lineage.ancestry(99).is_empty());
+        assert!(lineage.improvements(99).is_empty());
+    }
+
+    #[test]
+    fn improvements_are_new_bests_going_forwards() {
+        let lineage = family();
+        // 1 beats everything in generation 0, 4 beats 1, 5 doesn't beat 4
+        assert_eq!(lineage.improvements(5), HashSet::from([1, 4]));
+    }
+
+    #[test]
+    fn genomes_without_a_result_are_skipped() {
+        let mut lineage = family();
+        let mut record = GenomeRecord::new(3, 1);
+        record.parents = vec![5];
+        let id = lineage.add(record);
+        assert_eq!(lineage.improvements(id), HashSet::from([1, 4]));
+        lineage.set_result(id, 500, Some(40.0));
+        assert_eq!(lineage.improvements(id), HashSet::from([1, 4, id]));
+        assert_eq!(lineage.get(id).unwrap().best_lap, Some(40.0));
+    }
+
+    #[test]
+    fn champion_tree_is_kept_until_the_next_champion() {
+        let mut lineage = family();
+        lineage.set_champion(3);
+        let champion = lineage.champion.as_ref().unwrap();
+        assert_eq!(champion.ancestors, [3, 0, 1]);
+        assert_eq!(champion.improvements, HashSet::from([1]));
+
+        // later results don't change it until it's worked out again
+        lineage.set_result(3, 1000, None);
+        assert_eq!(
+            lineage.champion.as_ref().unwrap().improvements,
+            HashSet::from([1])
+        );
+        lineage.set_champion(3);
+        assert_eq!(
+            lineage.champion.as_ref().unwrap().improvements,
+            HashSet::from([1, 3])
+        );
+    }
+
+    #[test]
+    fn record_json_has_every_field() {
+        let mut record = GenomeRecord::new(1, 4);
+        record.parents = vec![0, 1];
+        record.crossovers = vec![(3, 1)];
+        record.migrated_from = Some(2);
+        record.best_lap = Some(41.5);
+        assert_eq!(
+            record.to_json(),
+            "{\"id\":0,\"generation\":1,\"car\":4,\"parents\":[0,1],\"crossovers\":[[3,1]],\"total_mutations\":0,\"partial_mutations\":0,\"migrated_from\":2,\"fitness\":null,\"best_lap\":41.500}"
+        );
+    }
+}
@@ -1,16 +1,44 @@
+// the codebase uses explicit returns and bracketed conditions throughout
+#![allow(clippy::needless_return, unused_parens)]
+
 use app::App;
 use macroquad::prelude::*;
 
+pub mod actuator;
 pub mod app;
+pub mod camera;
 pub mod car;
+pub mod car_spec;
+pub mod compare;
+pub mod config;
+pub mod crash_map;
+pub mod drive;
+pub mod elites;
+pub mod geometry;
+pub mod ghost;
+pub mod graph;
+pub mod hall_of_fame;
+pub mod headless;
+pub mod islands;
+pub mod lap_check;
+pub mod leaderboard;
+pub mod lineage;
 pub mod network;
+pub mod physics;
 pub mod population;
-pub mod timer;
+pub mod records;
+pub mod replay;
+pub mod replay_viewer;
+pub mod spatial;
+pub mod stats;
+pub mod svg;
+pub mod telemetry;
+pub mod telemetry_viewer;
 pub mod track;
 pub mod ui;
 pub mod utils;
 
-// constants
+// starting size of the window, it can be resized after
 pub const WINDOW_WIDTH: i32 = 1200;
 pub const WINDOW_HEIGHT: i32 = 800;
 
@@ -20,15 +48,24 @@ fn window_conf() -> Conf {
         window_title: "Racers".to_string(),
         window_width: WINDOW_WIDTH,
         window_height: WINDOW_HEIGHT,
-        window_resizable: false,
+        window_resizable: true,
         ..Default::default()
     }
 }
 
-#[macroquad::main(window_conf)]
-async fn main() {
+fn main() {
     macroquad::rand::srand(macroquad::miniquad::date::now() as _);
 
+    // `--headless <generations>` trains without opening a window
+    let args: Vec<String> = std::env::args().collect();
+    if let Some(generations) = headless::requested_generations(&args) {
+        headless::run(generations);
+        return;
+    }
+    macroquad::Window::from_config(window_conf(), run());
+}
+
+async fn run() {
     let mut app = App::new();
 
     loop {
@@ -1,5 +1,6 @@
+use crate::car::{NUM_INPUTS, NUM_OUTPUTS};
 use core::f64;
-use std::iter;
+use std::fs;
 
 #[derive(Clone)]
 pub struct Layer {
@@ -24,6 +25,90 @@ impl Network {
         return self;
     }
 
+    // every weight then every bias, layer by layer, for comparing networks
+    pub fn genome(&self) -> Vec<f64> {
+        let mut genes = vec![];
+        for layer in self.layers.iter() {
+            for row in layer.weights.iter() {
+                genes.extend_from_slice(row);
+            }
+            genes.extend_from_slice(&layer.bias);
+        }
+        return genes;
+    }
+
+    pub fn to_text(&self) -> String {
+        // one line per layer: activation, then the weights row by row, then the biases
+        // e.g. "sigmoid 3x2 w1 w2 w3 w4 w5 w6 | b1 b2"
+        let mut text = String::new();
+        for layer in self.layers.iter() {
+            let activation = match layer.activation {
+                Some(_) => "sigmoid",
+                None => "none",
+            };
+            let inputs = layer.weights.first().map_or(0, |row| row.len());
+            text += &format!("{} {}x{}", activation, inputs, layer.bias.len());
+            for row in layer.weights.iter() {
+                for weight in row.iter() {
+                    text += &format!(" {}", weight);
+                }
+            }
+            text += " |";
+            for bias in layer.bias.iter() {
+                text += &format!(" {}", bias);
+            }
+            text += "\n";
+        }
+        return text;
+    }
+
+    // None unless a car could drive with it, the layers have to chain from the car's
+    // inputs to at least its outputs or the first tick would panic
+    pub fn from_text(text: &str) -> Option<Self> {
+        let mut network = Self::new_empty();
+        let mut expected_inputs = NUM_INPUTS;
+        for line in text.lines().filter(|l| !l.trim().is_empty()) {
+            let (weights_part, bias_part) = line.split_once('|')?;
+            let mut words = weights_part.split_whitespace();
+
+            let activation: Option<fn(f64) -> f64> = match words.next()? {
+                "sigmoid" => Some(sigmoid),
+                _ => None,
+            };
+            let (inputs, outputs) = words.next()?.split_once('x')?;
+            let inputs: usize = inputs.parse().ok()?;
+            let outputs: usize = outputs.parse().ok()?;
+
+            let flat: Vec<f64> = words.map(|w| w.parse().ok()).collect::<Option<_>>()?;
+            let bias: Vec<f64> = bias_part
+                .split_whitespace()
+                .map(|w| w.parse().ok())
+                .collect::<Option<_>>()?;
+            if inputs != expected_inputs || flat.len() != inputs * outputs || bias.len() != outputs
+            {
+                return None;
+            }
+            expected_inputs = outputs;
+
+            let weights = flat.chunks(inputs).map(|row| row.to_vec()).collect();
+            network = network.add_layer(Layer::new(weights, bias, activation));
+        }
+        if network.layers.is_empty() || expected_inputs < NUM_OUTPUTS {
+            return None;
+        }
+        return Some(network);
+    }
+
+    pub fn save(&self, path: &str) {
+        if let Err(e) = fs::write(path, self.to_text()) {
+            println!("[network.rs] - Couldn't save network to {}: {}", path, e);
+        }
+    }
+
+    pub fn load(path: &str) -> Option<Self> {
+        return Self::from_text(&fs::read_to_string(path).ok()?);
+    }
+
     pub fn run(&mut self, inputs: Vec<f64>) -> Vec<f64> {
         // run first layer
         let mut prev_output: Vec<f64> = inputs;
@@ -47,9 +132,9 @@ impl Layer {
     pub fn new_random(inputs: usize, outputs: usize, activation: Option<fn(f64) -> f64>) -> Self {
         // generating the weights between 0.75 and -0.75
         let mut weights: Vec<Vec<f64>> = vec![];
-        for i in 0..outputs {
+        for _ in 0..outputs {
             let mut inner = vec![];
-            for j in 0..inputs {
+            for _ in 0..inputs {
                 let val = macroquad::rand::gen_range(-0.75, 0.75);
                 inner.push(val);
             }
@@ -57,7 +142,7 @@ impl Layer {
         }
 
         let mut bias: Vec<f64> = vec![];
-        for i in 0..outputs {
+        for _ in 0..outputs {
             let val = macroquad::rand::gen_range(0.25, -0.25);
             bias.push(val);
         }
@@ -72,17 +157,15 @@ impl Layer {
         }
 
         // create empty list of zeros
-        let mut outputs: Vec<f64> = iter::repeat(0.0).take(self.bias.len()).collect();
+        let mut outputs: Vec<f64> = vec![0.0; self.bias.len()];
 
         // perform the matrix multiplication of inputs and weights
-        for output_index in 0..outputs.len() {
+        for (output_index, output) in outputs.iter_mut().enumerate() {
             let bias = self.bias[output_index];
-            for input_index in 0..inputs.len() {
-                let input = inputs[input_index];
-                let weight = self.weights[output_index][input_index];
-                outputs[output_index] += weight * input;
+            for (input, weight) in inputs.iter().zip(self.weights[output_index].iter()) {
+                *output += weight * input;
             }
-            outputs[output_index] += bias;
+            *output += bias;
         }
 
         // if there is an activation function return the values with the function applied
@@ -96,8 +179,8 @@ impl Layer {
     }
 
     fn apply_activation(&self, mut inputs: Vec<f64>) -> Vec<f64> {
-        for i in 0..inputs.len() {
-            inputs[i] = self.activation.unwrap()(inputs[i]);
+        for input in inputs.iter_mut() {
+            *input = self.activation.unwrap()(*input);
         }
         return inputs;
     }
@@ -106,3 +189,88 @@ impl Layer {
 pub fn sigmoid(x: f64) -> f64 {
     return 1.0 / (1.0 + f64::consts::E.powf(-x));
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // the same shape as a car's brain
+    fn car_network() -> Network {
+        return Network::new_empty()
+            .add_layer(Layer::new_random(NUM_INPUTS, 4, None))
+            .add_layer(Layer::new_random(4, NUM_OUTPUTS, Some(sigmoid)));
+    }
+
+    // a line for a layer with every weight and bias set to the same value
+    fn layer_line(inputs: usize, outputs: usize, value: f64) -> String {
+        let weights = vec![value.to_string(); inputs * outputs].join(" ");
+        let bias = vec![value.to_string(); outputs].join(" ");
+        return format!("none {}x{} {} | {}\n", inputs, outputs, weights, bias);
+    }
+
+    #[test]
+    fn text_loads_back() {
+        let mut network = car_network();
+        let text = network.to_text();
+        let mut loaded = Network::from_text(&text).unwrap();
+        assert_eq!(loaded.genome(), network.genome());
+        assert_eq!(loaded.to_text(), text);
+
+        // the activations come back too so it drives the same
+        let inputs: Vec<f64> = (0..NUM_INPUTS).map(|i| i as f64 / 10.0).collect();
+        assert_eq!(loaded.run(inputs.clone()), network.run(inputs));
+    }
+
+    #[test]
+    fn blank_lines_are_ignored() {
+        let text = format!(
+            "\n{}\n  \n{}",
+            layer_line(NUM_INPUTS, 2, 0.5),
+            layer_line(2, 3, 0.5)
+        );
+        assert_eq!(Network::from_text(&text).unwrap().layers.len(), 2);
+    }
+
+    #[test]
+    fn empty_text_is_rejected() {
+        assert!(Network::from_text("").is_none());
+        assert!(Network::from_text("\n  \n").is_none());
+    }
+
+    #[test]
+    fn first_layer_must_take_the_car_inputs() {
+        let text = layer_line(NUM_INPUTS - 1, NUM_OUTPUTS, 0.1);
+        assert!(Network::from_text(&text).is_none());
+        assert!(Network::from_text(&layer_line(NUM_INPUTS, NUM_OUTPUTS, 0.1)).is_some());
+    }
+
+    #[test]
+    fn layers_must_chain() {
+        let text = layer_line(NUM_INPUTS, 4, 0.1) + &layer_line(5, NUM_OUTPUTS, 0.1);
+        assert!(Network::from_text(&text).is_none());
+    }
+
+    #[test]
+    fn last_layer_needs_every_output() {
+        let text = layer_line(NUM_INPUTS, 4, 0.1) + &layer_line(4, NUM_OUTPUTS - 1, 0.1);
+        assert!(Network::from_text(&text).is_none());
+        // extra outputs are ignored by the car
+        let text = layer_line(NUM_INPUTS, 4, 0.1) + &layer_line(4, NUM_OUTPUTS + 1, 0.1);
+        assert!(Network::from_text(&text).is_some());
+    }
+
+    #[test]
+    fn counts_must_match_the_header() {
+        let short = layer_line(NUM_INPUTS, NUM_OUTPUTS, 0.1).replacen(" 0.1", "", 1);
+        assert!(Network::from_text(&short).is_none());
+        let extra_bias = layer_line(NUM_INPUTS, NUM_OUTPUTS, 0.1)
+            .trim_end()
+            .to_string()
+            + " 0.1";
+        assert!(Network::from_text(&extra_bias).is_none());
+        let no_bar = layer_line(NUM_INPUTS, NUM_OUTPUTS, 0.1).replace('|', "");
+        assert!(Network::from_text(&no_bar).is_none());
+        let bad_number = layer_line(NUM_INPUTS, NUM_OUTPUTS, 0.1).replacen("0.1", "x", 1);
+        assert!(Network::from_text(&bad_number).is_none());
+    }
+}
@@ -0,0 +1,217 @@
+use macroquad::prelude::*;
+
+// below this speed the slip angles blow up so the dynamic model falls back to the kinematic one
+const LOW_SPEED: f32 = 60.0;
+// longest time step the dynamic model is integrated with, longer frames get split up
+const MAX_SUB_STEP: f32 = 1.0 / 480.0;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum PhysicsModel {
+    Arcade,    // the original lerped steering with flat friction
+    Kinematic, // bicycle model where the tyres never slip
+    Dynamic,   // bicycle model with slip angles and a traction circle
+}
+
+// all lengths are in pixels and times in seconds
+#[derive(Clone, Copy)]
+pub struct BicycleParams {
+    pub wheelbase: f32,
+    pub front_axle: f32, // distance from the centre of mass to the front axle
+    pub max_steer_angle: f32,
+    pub steer_falloff_speed: f32, // speed at which the steering lock has halved
+    pub mass: f32,
+    pub drive_force: f32,
+    pub braking_factor: f32,
+    pub drag: f32,
+    pub grip: f32,            // max acceleration the tyres can give (mu * g)
+    pub peak_slip_angle: f32, // slip angle where the tyre force stops rising
+}
+
+// inputs after the actuators, throttle and brake 0 to 1, steer -1 to 1
+#[derive(Clone, Copy, Default)]
+pub struct Controls {
+    pub throttle: f32,
+    pub steer: f32,
+    pub brake: f32,
+}
+
+#[derive(Clone, Copy)]
+pub struct VehicleState {
+    pub velocity: Vec2,
+    pub angle: f32,
+    pub yaw_rate: f32,
+    pub steer_angle: f32,
+    pub acceleration: Vec2,
+}
+
+impl BicycleParams {
+    pub fn rear_axle(&self) -> f32 {
+        return self.wheelbase - self.front_axle;
+    }
+
+    pub fn steering_angle(&self, steer: f32, speed: f32) -> f32 {
+        // less steering lock at speed, like a real driver would use
+        let lock = self.max_steer_angle / (1.0 + speed.abs() / self.steer_falloff_speed);
+        return clamp(steer, -1.0, 1.0) * lock;
+    }
+
+    fn longitudinal_force(&self, controls: &Controls, forward_speed: f32) -> f32 {
+        let drive = controls.throttle * self.drive_force;
+        let braking = -forward_speed * controls.brake * self.braking_factor * self.mass;
+        let drag = -forward_speed * self.drag * self.mass;
+        return drive + braking + drag;
+    }
+}
+
+pub fn step_kinematic(
+    state: &mut VehicleState,
+    controls: &Controls,
+    params: &BicycleParams,
+    dt: f32,
+) {
+    // a frame with no time in it would divide by zero working out the acceleration
+    if dt <= 0.0 {
+        return;
+    }
+    let heading = Vec2::from_angle(state.angle);
+    let mut speed = state.velocity.dot(heading);
+
+    let old_velocity = state.velocity;
+    speed += params.longitudinal_force(controls, speed) / params.mass * dt;
+
+    // yaw from the geometry of the wheels alone
+    state.steer_angle = params.steering_angle(controls.steer, speed);
+    state.yaw_rate = speed / params.wheelbase * state.steer_angle.tan();
+    state.angle += state.yaw_rate * dt;
+
+    // the centre of mass moves at an angle to the heading when turning
+    let slip = (params.rear_axle() / params.wheelbase * state.steer_angle.tan()).atan();
+    state.velocity = Vec2::from_angle(state.angle + slip) * speed;
+    state.acceleration = (state.velocity - old_velocity) / dt;
+}
+
+pub fn step_dynamic(
+    state: &mut VehicleState,
+    controls: &Controls,
+    params: &BicycleParams,
+    dt: f32,
+) {
+    if dt <= 0.0 {
+        return;
+    }
+    // the tyre forces are stiff so long frames are split into small steps
+    let steps = (dt / MAX_SUB_STEP).ceil().max(1.0);
+    let sub_dt = dt / steps;
+    for _ in 0..steps as usize {
+        if state.velocity.length() < LOW_SPEED {
+            step_kinematic(state, controls, params, sub_dt);
+        } else {
+            dynamic_sub_step(state, controls, params, sub_dt);
+        }
+    }
+}
+
+fn dynamic_sub_step(
+    state: &mut VehicleState,
+    controls: &Controls,
+    params: &BicycleParams,
+    dt: f32,
+) {
+    let a = params.front_axle;
+    let b = params.rear_axle();
+    let heading = Vec2::from_angle(state.angle);
+    let side = heading.perp();
+
+    // velocity in the car's frame
+    let vx = state.velocity.dot(heading);
+    let vy = state.velocity.dot(side);
+    let r = state.yaw_rate;
+
+    let delta = params.steering_angle(controls.steer, vx);
+    state.steer_angle = delta;
+
+    // each axle can give at most its share of the weight times the grip
+    let front_max = params.grip * params.mass * b / params.wheelbase;
+    let rear_max = params.grip * params.mass * a / params.wheelbase;
+    let front_stiffness = front_max / params.peak_slip_angle;
+    let rear_stiffness = rear_max / params.peak_slip_angle;
+
+    // slip angles of each axle
+    let alpha_front = (vy + a * r).atan2(vx.abs()) - delta * vx.signum();
+    let alpha_rear = (vy - b * r).atan2(vx.abs());
+
+    // rear wheel drive, the traction circle shares the rear grip between drive and cornering
+    let fx = clamp(params.longitudinal_force(controls, vx), -rear_max, rear_max);
+    let rear_lateral_max = (rear_max * rear_max - fx * fx).max(0.0).sqrt();
+    let fy_front = clamp(-front_stiffness * alpha_front, -front_max, front_max);
+    let fy_rear = clamp(
+        -rear_stiffness * alpha_rear,
+        -rear_lateral_max,
+        rear_lateral_max,
+    );
+
+    // equations of motion in the car's frame
+    let yaw_inertia = params.mass * a * b;
+    let ax = (fx - fy_front * delta.sin()) / params.mass + vy * r;
+    let ay = (fy_rear + fy_front * delta.cos()) / params.mass - vx * r;
+    let yaw_acc = (a * fy_front * delta.cos() - b * fy_rear) / yaw_inertia;
+
+    let new_vx = vx + ax * dt;
+    let new_vy = vy + ay * dt;
+    state.yaw_rate += yaw_acc * dt;
+    state.angle += state.yaw_rate * dt;
+
+    let old_velocity = state.velocity;
+    state.velocity = heading * new_vx + side * new_vy;
+    state.acceleration = (state.velocity - old_velocity) / dt;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::car_spec::CarSpec;
+
+    fn moving_state() -> VehicleState {
+        VehicleState {
+            velocity: vec2(300.0, 20.0),
+            angle: 0.1,
+            yaw_rate: 0.2,
+            steer_angle: 0.05,
+            acceleration: vec2(5.0, 1.0),
+        }
+    }
+
+    fn controls() -> Controls {
+        Controls {
+            throttle: 1.0,
+            steer: 0.5,
+            brake: 0.0,
+        }
+    }
+
+    #[test]
+    fn zero_frame_time_leaves_the_state_alone() {
+        let params = CarSpec::gt().bicycle_params();
+        for dt in [0.0, -0.01] {
+            let mut state = moving_state();
+            step_kinematic(&mut state, &controls(), &params, dt);
+            assert_eq!(state.velocity, moving_state().velocity);
+            assert_eq!(state.acceleration, moving_state().acceleration);
+
+            let mut state = moving_state();
+            step_dynamic(&mut state, &controls(), &params, dt);
+            assert_eq!(state.velocity, moving_state().velocity);
+            assert_eq!(state.acceleration, moving_state().acceleration);
+        }
+    }
+
+    #[test]
+    fn a_normal_frame_stays_finite() {
+        let params = CarSpec::gt().bicycle_params();
+        let mut state = moving_state();
+        step_dynamic(&mut state, &controls(), &params, 1.0 / 60.0);
+        assert!(state.velocity.is_finite());
+        assert!(state.acceleration.is_finite());
+        assert!(state.angle.is_finite());
+    }
+}
@@ -1,11 +1,25 @@
+use crate::camera::Camera;
 use crate::car::*;
-use crate::timer::*;
+use crate::car_spec::CarClass;
+use crate::config::SimConfig;
+use crate::crash_map::{CrashEvent, CrashMap};
+use crate::elites::Elites;
+use crate::ghost::*;
+use crate::graph::FitnessGraph;
+use crate::leaderboard::Leaderboard;
+use crate::lineage::*;
+use crate::network::Network;
+use crate::records::LapRecord;
+use crate::replay::Replay;
+use crate::stats::{GenerationStats, RunLog};
+use crate::svg::REPORT_NAME;
+use crate::telemetry::*;
 use crate::track::*;
-use crate::{WINDOW_HEIGHT, WINDOW_WIDTH};
 use macroquad::prelude::*;
-use macroquad::rand::gen_range;
-use std::fs::File;
-use std::io::prelude::*;
+use macroquad::rand::{gen_range, srand};
+
+// the best network of the latest generation, so it can be raced in drive mode
+pub const CHAMPION_PATH: &str = "champion.net";
 
 // mutation rates
 // P - partial T - total
@@ -14,47 +28,193 @@ const WEIGHT_MUT_P: f32 = 0.07;
 const BIAS_MUT_T: f32 = 0.03;
 const BIAS_MUT_P: f32 = 0.05;
 
+const SELECTED_COLOUR: Color = color_u8!(0, 200, 255, 255);
+const BEST_TRAIL_COLOUR: Color = color_u8!(255, 215, 0, 255);
+// the other cars only show a short tail so hundreds of trails stay readable
+const SHORT_TRAIL_TICKS: usize = 60;
+
+// which cars draw the path they've driven this generation, T cycles through them
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum TrailMode {
+    Best,
+    All,
+    Off,
+}
+
 pub struct Population {
     generation: usize,
     cars: Vec<Car>,
+    classes: Vec<CarClass>, // cars take turns between the classes when there's more than one
     track: Track,
     ticks: u32,
-    timer: Timer,
-    data_file: File,
-    time_limit: u32,
+    leaderboard: Leaderboard,
+    ghost: Option<Ghost>,   // fastest lap so far on this track, from any session
+    replay: Option<Replay>, // the generation being recorded, if replays are on
+    run_log: Option<RunLog>, // per generation stats for this run, None for drive mode opponents
+    history: Vec<GenerationStats>, // the same stats kept for the graph
+    graph: FitnessGraph,
+    training_panels: bool, // off for drive mode opponents, the drive session has the top left
+    crash_map: CrashMap,   // where cars came off, over the whole run
+    trail_mode: TrailMode,
+    lineage: Lineage,
+    elites: Elites,                   // best genomes of the whole run
+    migrants: Vec<(Network, usize)>,  // from other islands and the island they left
+    racing_line: Vec<Vec2>,           // the fastest valid lap of this run, for the report
+    champion: Option<(Network, i32)>, // best of the last finished generation and its fitness
+    config: SimConfig,
 }
 
 impl Population {
-    pub fn new(size: usize, time_limit: u32) -> Self {
-        let track: Track = Track::new(test_track1, 100.0);
+    // None if the track in the config doesn't exist
+    pub fn new(config: SimConfig) -> Option<Self> {
+        return Self::create(config, true);
+    }
+
+    // cars for the player to race against in drive mode, they don't get a run folder
+    // so drive sessions don't show up next to the training runs, and the graph and
+    // other training panels stay hidden under the drive session's own hud
+    pub fn opponents(config: SimConfig) -> Option<Self> {
+        return Self::create(config, false);
+    }
+
+    fn create(config: SimConfig, training: bool) -> Option<Self> {
+        // the same seed gives the same first generation
+        srand(config.seed);
+
+        let Some(mut track) = Track::from_id(&config.track_id) else {
+            println!("[population.rs] - Unknown track {}", config.track_id);
+            return None;
+        };
+        let ghost = Ghost::load(&track.id);
+        if let Some(ghost) = &ghost {
+            track.set_racing_line(ghost.get_line());
+        }
+        let classes: Vec<CarClass> = config
+            .car_classes
+            .iter()
+            .map(|s| match config.headless {
+                true => s.headless_class(),
+                false => s.load_class(),
+            })
+            .collect();
         let mut cars = vec![];
-        for i in 0..size {
+        for i in 0..config.population_size {
             // set car numbers as i+1, e.g. first car will get number 1
-            cars.push(Car::new(track.get_start_pos(), i + 1));
+            cars.push(Car::new(
+                track.get_start_pos(),
+                i + 1,
+                &classes[i % classes.len()],
+            ));
+        }
+
+        let run_log = match training {
+            true => Some(RunLog::create(&config, &track.id)),
+            false => None,
+        };
+        let crash_map = CrashMap::new(track.checkpoint_count());
+
+        // the first generation is random so it has no parents
+        let mut lineage = Lineage::new();
+        for car in cars.iter_mut() {
+            car.genome_id = lineage.add(GenomeRecord::new(0, car.number));
         }
 
-        Self {
+        let replay = match config.record_replays {
+            true => Some(Replay::start(&config, &track.id, 0, &cars)),
+            false => None,
+        };
+
+        return Some(Self {
             generation: 0,
             cars,
+            classes,
             track,
             ticks: 0,
-            time_limit,
-            timer: Timer::new(),
-            data_file: File::create("fitness_values_test1.csv").unwrap(),
+            config,
+            leaderboard: Leaderboard::default(),
+            ghost,
+            replay,
+            run_log,
+            history: vec![],
+            graph: FitnessGraph::new(20.0, 20.0),
+            training_panels: training,
+            crash_map,
+            trail_mode: TrailMode::Best,
+            lineage,
+            elites: Elites::new(),
+            migrants: vec![],
+            racing_line: vec![],
+            champion: None,
+        });
+    }
+
+    pub fn get_track(&self) -> &Track {
+        return &self.track;
+    }
+
+    pub fn get_best_car(&self) -> Option<&Car> {
+        // find best performer
+        let mut best: Option<&Car> = None;
+        for car in self.cars.iter() {
+            if best.is_none_or(|b| car.fitness > b.fitness) {
+                best = Some(car);
+            }
         }
+        return best;
+    }
+
+    // the best network of the last finished generation and its fitness
+    pub fn get_champion(&self) -> Option<(&Network, i32)> {
+        return self
+            .champion
+            .as_ref()
+            .map(|(brain, fitness)| (brain, *fitness));
+    }
+
+    pub fn get_generation(&self) -> usize {
+        return self.generation;
+    }
+
+    // how the last finished generation did, None during the first one
+    pub fn get_last_stats(&self) -> Option<&GenerationStats> {
+        return self.history.last();
     }
 
-    pub fn draw(&self) {
+    // copies of the island's best genomes so far, for sending to another island
+    pub fn get_migrants(&self, count: usize) -> Vec<Network> {
+        return self
+            .elites
+            .get_entries()
+            .iter()
+            .take(count)
+            .map(|e| e.brain.clone())
+            .collect();
+    }
+
+    // they join the next generation in place of some of the children
+    pub fn receive_migrants(&mut self, brains: Vec<Network>, from: usize) {
+        self.migrants
+            .extend(brains.into_iter().map(|brain| (brain, from)));
+    }
+
+    // drawn through the camera
+    pub fn draw_world(&self) {
         self.track.draw();
+        self.crash_map.draw(&self.track);
 
-        // find best performer
-        let mut best_fitness = -1000000; // close enough to - infinity
-        let mut best_car_number: usize = 0;
-        for i in 0..self.cars.len() {
-            let car = &self.cars[i];
-            if car.fitness > best_fitness {
-                best_fitness = car.fitness;
-                best_car_number = car.number;
+        // the ghost restarts its lap with every generation
+        if let (Some(ghost), Some(texture)) = (&self.ghost, &self.classes[0].texture) {
+            ghost.draw(self.ticks as usize, texture);
+        }
+
+        let best_car_number = self.get_best_car().map_or(0, |c| c.number);
+
+        // trails go under the cars, the best car's covers the whole generation
+        for car in self.cars.iter() {
+            if car.number == best_car_number && self.trail_mode != TrailMode::Off {
+                car.draw_trail(usize::MAX, BEST_TRAIL_COLOUR);
+            } else if self.trail_mode == TrailMode::All && !car.crashed {
+                car.draw_trail(SHORT_TRAIL_TICKS, car.spec.tint);
             }
         }
 
@@ -68,36 +228,169 @@ impl Population {
             }
         }
 
+        // ring the cars being logged
+        if self.config.telemetry == TelemetryMode::Selected {
+            for car in self.cars.iter().filter(|c| c.telemetry_on) {
+                let centre = car.get_centre();
+                let radius = car.get_size().max_element() * 0.75;
+                // a thin ring until the car starts a lap and logging begins
+                let thickness = match car.is_telemetry_waiting() {
+                    true => 1.0,
+                    false => 3.0,
+                };
+                draw_circle_lines(centre.x, centre.y, radius, thickness, SELECTED_COLOUR);
+            }
+        }
+    }
+
+    // drawn in screen pixels on top of the world
+    pub fn draw_hud(&self) {
         // draw the generation number
         let text = format!("Generation: {}", self.generation);
         let colour = color_u8!(20, 20, 20, 100);
-        draw_text(&text, 400.0, 350.0, 75.0, colour);
+        draw_text(
+            &text,
+            screen_width() / 2.0 - 200.0,
+            screen_height() / 2.0 - 50.0,
+            75.0,
+            colour,
+        );
 
         // draw the timer bar
         self.draw_timer_bar();
+
+        // fastest laps in the top right
+        self.leaderboard.draw(screen_width() - 540.0, 20.0);
+
+        if !self.training_panels {
+            return;
+        }
+
+        // how training is going in the top left
+        self.graph.draw(&self.history);
+        self.lineage.draw();
+        self.elites.draw();
+
+        if self.crash_map.visible {
+            let worst = match self.crash_map.worst_sector() {
+                Some((sector, count)) => format!(", worst is sector {} with {}", sector, count),
+                None => String::new(),
+            };
+            let text = format!(
+                "Crash heatmap (H): {} crashes{}",
+                self.crash_map.total(),
+                worst
+            );
+            draw_text(&text, 20.0, screen_height() - 130.0, 25.0, BLACK);
+        }
+
+        if self.config.telemetry == TelemetryMode::Selected {
+            let selected = self.cars.iter().filter(|c| c.telemetry_on).count();
+            let text = format!("Click cars to log their telemetry ({} logged)", selected);
+            draw_text(&text, 20.0, screen_height() - 100.0, 25.0, BLACK);
+        }
     }
 
     pub fn update(&mut self) {
-        let dt = get_frame_time();
+        if self.training_panels {
+            self.graph.update();
+            self.crash_map.update();
+            self.lineage.update();
+            self.elites.update();
+        }
+        if is_key_pressed(KeyCode::T) {
+            self.trail_mode = match self.trail_mode {
+                TrailMode::Best => TrailMode::All,
+                TrailMode::All => TrailMode::Off,
+                TrailMode::Off => TrailMode::Best,
+            };
+        }
+        self.step(get_frame_time());
+    }
 
-        if self.ticks >= self.time_limit || self.all_cars_crashed() {
+    // one tick of the race, kept apart from the keys so it can run without a window
+    pub fn step(&mut self, dt: f32) {
+        if self.ticks >= self.config.time_limit || self.all_cars_crashed() {
             self.new_population();
         }
+        if self.config.telemetry == TelemetryMode::FastestLaps {
+            self.follow_leader();
+        }
 
         for car in self.cars.iter_mut() {
-            car.update(&self.track, dt);
-            if !car.is_on_track(&self.track) {
-                car.crashed();
+            let (was_crashed, wall_hits) = (car.crashed, car.wall_hits);
+            car.update(&self.track, dt, self.config.physics_model);
+            car.apply_track_limits(
+                &self.track,
+                self.config.collision_mode,
+                self.config.off_track_rule,
+            );
+
+            // note where it went wrong for the heatmap
+            let crashed = car.crashed && !was_crashed;
+            if crashed || car.wall_hits > wall_hits {
+                self.crash_map.add(CrashEvent {
+                    generation: self.generation,
+                    car_number: car.number,
+                    position: car.get_centre(),
+                    sector: car.get_sector(&self.track) as usize,
+                    wall_hit: !crashed,
+                });
             }
 
             if car.just_lapped {
-                self.timer
-                    .enter_time((car.number, self.generation, car.lap_time as f32));
-                print!("{esc}c", esc = 27 as char);
-                self.timer.print_times();
+                let invalid = car.last_lap_invalid.map(|reason| reason.to_string());
+                let place = self
+                    .leaderboard
+                    .submit(car.get_lap_entry(self.generation), invalid.clone());
+                if place == Some(0) {
+                    self.racing_line = car.get_last_lap_trace().iter().map(|f| f.0).collect();
+                    if record_if_faster(&mut self.ghost, &self.track.id, car) {
+                        if let Some(ghost) = &self.ghost {
+                            self.track.set_racing_line(ghost.get_line());
+                        }
+                    }
+                    LapRecord::from_car(&self.track.id, self.config.seed, self.generation, car)
+                        .save();
+                }
+                let telemetry = car.take_last_lap_telemetry();
+                let keep = match self.config.telemetry {
+                    TelemetryMode::Off => false,
+                    TelemetryMode::FastestLaps => place == Some(0),
+                    TelemetryMode::Selected => car.telemetry_on,
+                };
+                if let (true, false, Some(log)) = (keep, telemetry.is_empty(), &self.run_log) {
+                    let lap = TelemetryLap::new(
+                        self.generation,
+                        car.number,
+                        car.laps,
+                        invalid.is_some(),
+                        telemetry,
+                    );
+                    lap.save(&log.dir);
+                }
+                if self.config.log_laps {
+                    match &invalid {
+                        Some(reason) => println!(
+                            "LAP - car {} gen {} {:.3}s (invalid: {})",
+                            car.number, self.generation, car.lap_seconds, reason
+                        ),
+                        None => println!(
+                            "LAP - car {} gen {} {:.3}s",
+                            car.number, self.generation, car.lap_seconds
+                        ),
+                    }
+                    if place.is_some() {
+                        self.leaderboard.print_times();
+                    }
+                }
             }
         }
 
+        if let Some(replay) = &mut self.replay {
+            replay.record_tick(&self.cars);
+        }
+
         self.ticks += 1;
     }
 
@@ -112,13 +405,69 @@ impl Population {
                 .cmp(&a.get_final_fitness(self.ticks + 1))
         });
 
-        for i in 0..(size) {
-            cars.push(self.reproduce(&self.cars[0], &self.cars[1], i));
+        for car in self.cars.iter() {
+            self.elites
+                .submit(car, self.generation, car.get_final_fitness(self.ticks + 1));
+        }
+
+        // the family tree gets this generation's results before the children are added
+        // elites that raced again get their average rather than the latest score
+        for car in self.cars.iter() {
+            let fitness = match self.elites.fitness_of(car.genome_id) {
+                Some(mean) => mean.round() as i32,
+                None => car.get_final_fitness(self.ticks + 1),
+            };
+            self.lineage
+                .set_result(car.genome_id, fitness, car.best_lap_seconds);
+        }
+        self.lineage.set_champion(self.cars[0].genome_id);
+
+        // copies of the elites race again, so a champion can't be lost to a bad
+        // crossover and a lucky score gets averaged out
+        let elite_count = match self.config.reinject_elites {
+            true => self.elites.len().min(size / 4),
+            false => 0,
+        };
+        for i in 0..elite_count {
+            cars.push(self.elite_car(i));
+        }
+
+        // then genomes sent over from another island
+        let migrants: Vec<(Network, usize)> = self.migrants.drain(..).collect();
+        for (brain, from) in migrants.into_iter().take(size / 4) {
+            let i = cars.len();
+            let mut car = Car::new(
+                self.track.get_start_pos(),
+                i + 1,
+                &self.classes[i % self.classes.len()],
+            );
+            car.brain = brain;
+            let mut record = GenomeRecord::new(self.generation + 1, i + 1);
+            record.migrated_from = Some(from);
+            car.genome_id = self.lineage.add(record);
+            cars.push(car);
         }
 
-        // add data to csv file
-        let best_fitness = self.cars[0].get_final_fitness(self.ticks + 1);
-        writeln!(self.data_file, "{},{}", self.generation, best_fitness).unwrap();
+        for i in cars.len()..(size) {
+            let (parent1, parent2) = self.pick_parents();
+            let (mut child, record) = self.reproduce(&self.cars[parent1], &self.cars[parent2], i);
+            child.genome_id = self.lineage.add(record);
+            cars.push(child);
+        }
+
+        // kept for the islands to save, only the best island's goes in champion.net
+        let champion = &self.cars[0];
+        self.champion = Some((
+            champion.brain.clone(),
+            champion.get_final_fitness(self.ticks + 1),
+        ));
+
+        // add data to the run's csv file
+        let stats = GenerationStats::from_cars(self.generation, &self.cars, self.ticks + 1);
+        let best_fitness = stats.best;
+        self.history.push(stats);
+        self.save_run();
+        self.crash_map.end_generation();
 
         println!(
             "GEN [{}] - Best Fitness = {}",
@@ -130,6 +479,70 @@ impl Population {
         self.ticks = 0;
 
         self.generation += 1;
+
+        // save the generation that just finished and start recording the new one
+        if let Some(replay) = &mut self.replay {
+            replay.save();
+            *replay = Replay::start(&self.config, &self.track.id, self.generation, &self.cars);
+        }
+    }
+
+    // everything the run folder keeps about the generation that just finished
+    fn save_run(&mut self) {
+        let Some(log) = &mut self.run_log else {
+            return;
+        };
+        if let Some(stats) = self.history.last() {
+            log.write(stats);
+        }
+        self.elites.save(&log.dir);
+        self.lineage.save_generation(&log.dir, self.generation);
+        self.lineage.save_ancestry(&log.dir);
+        self.crash_map.save_generation(&log.dir);
+
+        // the report is redrawn every generation so the folder always has an up to date picture
+        let title = format!(
+            "{} - {} - generation {}",
+            log.dir, self.track.id, self.generation
+        );
+        let report = crate::svg::report(&title, &self.track, &self.history, &self.racing_line);
+        report.save(&format!("{}/{}", log.dir, REPORT_NAME));
+    }
+
+    // clicking a car in the simulation view logs its telemetry
+    pub fn update_selection(&mut self, camera: &Camera) {
+        if self.config.telemetry == TelemetryMode::Selected
+            && is_mouse_button_pressed(MouseButton::Left)
+        {
+            self.toggle_car_at(camera.screen_to_world(Vec2::from(mouse_position())));
+        }
+    }
+
+    // starts or stops logging the car under the mouse, crashed cars can't be picked
+    fn toggle_car_at(&mut self, point: Vec2) {
+        for car in self.cars.iter_mut() {
+            let radius = car.get_size().max_element() / 2.0;
+            if !car.crashed && car.get_centre().distance(point) <= radius {
+                let on = !car.telemetry_on;
+                car.set_telemetry(on);
+                return;
+            }
+        }
+    }
+
+    // only the car in front logs, its lap is the one most likely to go top of the
+    // leaderboard, a new leader starts logging from its next lap
+    fn follow_leader(&mut self) {
+        let mut leader: Option<&Car> = None;
+        for car in self.cars.iter().filter(|c| !c.crashed) {
+            if leader.is_none_or(|l| car.fitness > l.fitness) {
+                leader = Some(car);
+            }
+        }
+        let leader = leader.map(|c| c.number);
+        for car in self.cars.iter_mut() {
+            car.set_telemetry(Some(car.number) == leader);
+        }
     }
 
     fn all_cars_crashed(&self) -> bool {
@@ -141,10 +554,44 @@ impl Population {
         return true;
     }
 
-    fn reproduce(&self, car1: &Car, car2: &Car, i: usize) -> Car {
-        let mut child_car = Car::new(self.track.get_start_pos(), i + 1);
+    // indexes of two different cars from the top of the sorted generation
+    // the best two always breed unless the pool is bigger
+    fn pick_parents(&self) -> (usize, usize) {
+        let pool = self.config.parent_pool.min(self.cars.len());
+        if pool <= 2 {
+            return (0, 1);
+        }
+        let parent1 = gen_range(0, pool);
+        let mut parent2 = gen_range(0, pool - 1);
+        if parent2 >= parent1 {
+            parent2 += 1;
+        }
+        return (parent1, parent2);
+    }
+
+    // a new car driven by the elite genome at a rank, in the class it raced in
+    fn elite_car(&self, rank: usize) -> Car {
+        let elite = &self.elites.get_entries()[rank];
+        let class = self
+            .classes
+            .iter()
+            .find(|c| c.spec.name == elite.class_name)
+            .unwrap_or(&self.classes[rank % self.classes.len()]);
+        let mut car = Car::new(self.track.get_start_pos(), rank + 1, class);
+        car.brain = elite.brain.clone();
+        car.genome_id = elite.genome_id;
+        return car;
+    }
+
+    // the child and a record of how it was made, for the family tree
+    fn reproduce(&self, car1: &Car, car2: &Car, i: usize) -> (Car, GenomeRecord) {
+        let class = &self.classes[i % self.classes.len()];
+        let mut child_car = Car::new(self.track.get_start_pos(), i + 1, class);
+        let mut record = GenomeRecord::new(self.generation + 1, i + 1);
+        record.parents = vec![car1.genome_id, car2.genome_id];
         let mut child_net = car1.brain.clone();
         let network2 = &car2.brain;
+        let scale = self.config.mutation_scale;
 
         // apply cross over
         for i in 0..child_net.layers.len() {
@@ -159,6 +606,9 @@ impl Population {
             let biases_crossover = gen_range(0, biases_size - 1);
 
             let child_layer = &mut child_net.layers[i];
+            record
+                .crossovers
+                .push((weights_crossover, biases_crossover));
 
             // cross over the weights
             for j in 0..=weights_crossover {
@@ -167,50 +617,51 @@ impl Population {
             }
 
             // cross over the biases
-            for k in 0..=biases_crossover {
-                let new_bias = biases2[k];
-                child_layer.bias[k] = new_bias;
-            }
+            child_layer.bias[..=biases_crossover].copy_from_slice(&biases2[..=biases_crossover]);
 
             // apply mutations
             for row in child_layer.weights.iter_mut() {
                 for weight in row.iter_mut() {
-                    if gen_range(0.0, 1.0) <= WEIGHT_MUT_T {
+                    if gen_range(0.0, 1.0) <= WEIGHT_MUT_T * scale {
                         *weight = gen_range(-1.0, 1.0);
+                        record.total_mutations += 1;
                     }
-                    if gen_range(0.0, 1.0) <= WEIGHT_MUT_P {
+                    if gen_range(0.0, 1.0) <= WEIGHT_MUT_P * scale {
                         *weight += gen_range(-0.5, 0.5);
+                        record.partial_mutations += 1;
                     }
                 }
             }
 
             for bias in child_layer.bias.iter_mut() {
-                if (gen_range(0.0, 1.0)) <= BIAS_MUT_T {
+                if (gen_range(0.0, 1.0)) <= BIAS_MUT_T * scale {
                     *bias = gen_range(-0.5, 0.5);
+                    record.total_mutations += 1;
                 }
-                if (gen_range(0.0, 1.0) <= BIAS_MUT_P) {
+                if (gen_range(0.0, 1.0) <= BIAS_MUT_P * scale) {
                     *bias += gen_range(-0.5, 0.5);
+                    record.partial_mutations += 1;
                 }
             }
         }
 
         child_car.brain = child_net;
 
-        return child_car;
+        return (child_car, record);
     }
 
     pub fn draw_timer_bar(&self) {
         // set the height of the bar
         let height = 30.0;
-        let draw_pos = Vec2::new(0.0, WINDOW_HEIGHT as f32 - height);
+        let draw_pos = Vec2::new(0.0, screen_height() - height);
 
-        let width = (WINDOW_WIDTH as f32) * (self.ticks as f32) / (self.time_limit as f32);
+        let width = screen_width() * (self.ticks as f32) / (self.config.time_limit as f32);
 
         // draw the rectangle with corresponding width and height
         draw_rectangle(
             draw_pos.x,
             draw_pos.y,
-            WINDOW_WIDTH as f32 - width,
+            screen_width() - width,
             height,
             YELLOW,
         );
@@ -219,9 +670,31 @@ impl Population {
         draw_text(
             "Generation Time Left:",
             10.0,
-            WINDOW_HEIGHT as f32 - height - 30.0,
+            screen_height() - height - 30.0,
             35.0,
             BLACK,
         );
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn trains_without_a_window() {
+        let config = SimConfig {
+            population_size: 10,
+            time_limit: 30,
+            headless: true,
+            ..SimConfig::default()
+        };
+        // no run folder, so the test leaves nothing behind
+        let mut population = Population::create(config, false).unwrap();
+        while population.get_generation() < 2 {
+            population.step(1.0 / 60.0);
+        }
+        assert_eq!(population.cars.len(), 10);
+        assert!(population.get_champion().is_some());
+    }
+}
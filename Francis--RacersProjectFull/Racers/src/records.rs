@@ -0,0 +1,212 @@
+use crate::car::Car;
+use std::fs;
+use std::fs::OpenOptions;
+use std::io::prelude::*;
+
+pub const RECORDS_DIR: &str = "records";
+const HEADER: &str = "run,generation,car,class,lap_seconds,timestamp,genome";
+
+// one fastest lap, kept forever
+// a run's records are the laps that beat everything else in that run so far
+#[derive(Clone)]
+pub struct LapRecord {
+    pub track_id: String,
+    pub run_id: u64, // the seed of the run
+    pub generation: usize,
+    pub car_number: usize,
+    pub class: String,
+    pub lap_seconds: f32,
+    pub timestamp: u64, // unix seconds
+    pub genome: String, // Network::to_text, empty when a person was driving
+}
+
+// every record on every track, one csv file per track
+pub struct RecordDb {
+    records: Vec<LapRecord>,
+}
+
+impl LapRecord {
+    pub fn from_car(track_id: &str, run_id: u64, generation: usize, car: &Car) -> Self {
+        Self {
+            track_id: track_id.to_string(),
+            run_id,
+            generation,
+            car_number: car.number,
+            class: car.spec.name.clone(),
+            lap_seconds: car.lap_seconds,
+            timestamp: macroquad::miniquad::date::now() as u64,
+            genome: car.brain.to_text(),
+        }
+    }
+
+    pub fn is_human(&self) -> bool {
+        return self.genome.is_empty();
+    }
+
+    fn path(track_id: &str) -> String {
+        return format!("{}/{}.csv", RECORDS_DIR, track_id);
+    }
+
+    fn to_line(&self) -> String {
+        // the genome has no commas, its lines are joined with ;
+        // class names come from cars.cfg so any commas in them are swapped for ; too
+        return format!(
+            "{},{},{},{},{},{},{}",
+            self.run_id,
+            self.generation,
+            self.car_number,
+            self.class.replace(',', ";"),
+            self.lap_seconds,
+            self.timestamp,
+            self.genome.trim_end().replace('\n', ";")
+        );
+    }
+
+    fn from_line(track_id: &str, line: &str) -> Option<Self> {
+        let parts: Vec<&str> = line.splitn(7, ',').collect();
+        let [run_id, generation, car_number, class, lap_seconds, timestamp, genome] = parts[..]
+        else {
+            return None;
+        };
+        Some(Self {
+            track_id: track_id.to_string(),
+            run_id: run_id.parse().ok()?,
+            generation: generation.parse().ok()?,
+            car_number: car_number.parse().ok()?,
+            class: class.replace(';', ","),
+            lap_seconds: lap_seconds.parse().ok()?,
+            timestamp: timestamp.parse().ok()?,
+            genome: genome.replace(';', "\n"),
+        })
+    }
+
+    // adds the record to the end of its track's file
+    pub fn save(&self) {
+        let path = Self::path(&self.track_id);
+        let result = fs::create_dir_all(RECORDS_DIR).and_then(|_| {
+            let new_file = !fs::exists(&path)?;
+            let mut file = OpenOptions::new().create(true).append(true).open(&path)?;
+            if new_file {
+                writeln!(file, "{}", HEADER)?;
+            }
+            writeln!(file, "{}", self.to_line())
+        });
+        if let Err(e) = result {
+            println!("[records.rs] - Couldn't save lap record to {}: {}", path, e);
+        }
+    }
+}
+
+impl RecordDb {
+    pub fn load() -> Self {
+        let mut records = vec![];
+        let entries = fs::read_dir(RECORDS_DIR).into_iter().flatten().flatten();
+        for entry in entries {
+            let path = entry.path();
+            if path.extension().is_none_or(|ext| ext != "csv") {
+                continue;
+            }
+            let Some(track_id) = path.file_stem().map(|s| s.to_string_lossy().to_string()) else {
+                continue;
+            };
+            let Ok(text) = fs::read_to_string(&path) else {
+                println!("[records.rs] - Couldn't read {}", path.display());
+                continue;
+            };
+            for line in text.lines().skip(1) {
+                match LapRecord::from_line(&track_id, line) {
+                    Some(record) => records.push(record),
+                    None => println!("[records.rs] - Bad record in {}: {}", path.display(), line),
+                }
+            }
+        }
+        return Self { records };
+    }
+
+    pub fn track_ids(&self) -> Vec<String> {
+        let mut ids: Vec<String> = self.records.iter().map(|r| r.track_id.clone()).collect();
+        ids.sort();
+        ids.dedup();
+        return ids;
+    }
+
+    // every record on the track, fastest first
+    pub fn on_track(&self, track_id: &str) -> Vec<&LapRecord> {
+        let mut records: Vec<&LapRecord> = self
+            .records
+            .iter()
+            .filter(|r| r.track_id == track_id)
+            .collect();
+        records.sort_by(|a, b| a.lap_seconds.total_cmp(&b.lap_seconds));
+        return records;
+    }
+
+    pub fn best_on_track(&self, track_id: &str) -> Option<&LapRecord> {
+        return self.on_track(track_id).first().copied();
+    }
+
+    // the fastest record of each run, fastest first
+    pub fn best_per_run(&self, track_id: &str) -> Vec<&LapRecord> {
+        return best_of_each(self.on_track(track_id), |r| r.run_id.to_string());
+    }
+
+    // the fastest record of each car class, fastest first
+    pub fn best_by_class(&self, track_id: &str) -> Vec<&LapRecord> {
+        return best_of_each(self.on_track(track_id), |r| r.class.clone());
+    }
+}
+
+// keeps the first record of each group, so the input has to be sorted fastest first
+fn best_of_each(records: Vec<&LapRecord>, key: impl Fn(&LapRecord) -> String) -> Vec<&LapRecord> {
+    let mut seen: Vec<String> = vec![];
+    let mut best = vec![];
+    for record in records {
+        let k = key(record);
+        if !seen.contains(&k) {
+            seen.push(k);
+            best.push(record);
+        }
+    }
+    return best;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(class: &str) -> LapRecord {
+        LapRecord {
+            track_id: "test_track1".to_string(),
+            run_id: 42,
+            generation: 5,
+            car_number: 12,
+            class: class.to_string(),
+            lap_seconds: 17.25,
+            timestamp: 1_700_000_000,
+            genome: "sigmoid 2x1 0.1 0.2 | 0.3\nnone 1x1 0.4 | 0.5\n".to_string(),
+        }
+    }
+
+    #[test]
+    fn line_loads_back() {
+        let original = record("GT");
+        let loaded = LapRecord::from_line("test_track1", &original.to_line()).unwrap();
+        assert_eq!(loaded.run_id, 42);
+        assert_eq!(loaded.generation, 5);
+        assert_eq!(loaded.car_number, 12);
+        assert_eq!(loaded.class, "GT");
+        assert_eq!(loaded.lap_seconds, 17.25);
+        // the last newline isn't stored
+        assert_eq!(loaded.genome.trim_end(), original.genome.trim_end());
+    }
+
+    #[test]
+    fn class_with_commas_loads_back() {
+        let original = record("Touring, heavy");
+        let loaded = LapRecord::from_line("test_track1", &original.to_line()).unwrap();
+        assert_eq!(loaded.class, "Touring, heavy");
+        assert_eq!(loaded.lap_seconds, 17.25);
+        // the last newline isn't stored
+        assert_eq!(loaded.genome.trim_end(), original.genome.trim_end());
+    }
+}
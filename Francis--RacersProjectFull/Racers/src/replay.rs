@@ -0,0 +1,387 @@
+use crate::car::Car;
+use crate::car_spec::{CarClass, CarSpec};
+use crate::config::SimConfig;
+use macroquad::prelude::*;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+pub const REPLAY_DIR: &str = "replays";
+const REPLAY_EXTENSION: &str = "replay";
+// older replays are deleted so a long run doesn't fill the disk
+const REPLAYS_KEPT: usize = 20;
+const MAGIC: &[u8; 4] = b"RPL1";
+
+// the frame time changes the physics, so a generation can't be re-simulated exactly
+// instead every car's state is stored on every tick
+#[derive(Clone, Copy)]
+pub struct CarFrame {
+    pub position: Vec2, // centre of the car
+    pub angle: f32,
+    pub crashed: bool,
+}
+
+// what the viewer needs to draw one class of car
+#[derive(Clone)]
+pub struct ReplayClass {
+    pub name: String,
+    pub sprite: String,
+    pub size: Vec2,
+    pub tint: Color,
+}
+
+#[derive(Clone)]
+pub struct ReplayCar {
+    pub number: usize,
+    pub class: usize,   // index into the classes
+    pub genome: String, // the network at the start of the generation, see Network::to_text
+}
+
+// one whole generation
+// file layout: magic, u32 header length, the text header, u32 ticks, u32 cars,
+// then x y angle as f32 and crashed as a u8 for each car on each tick, all little endian
+pub struct Replay {
+    pub track_id: String,
+    pub seed: u64,
+    pub generation: usize,
+    pub settings: Vec<(String, String)>, // the rest of the config, for showing in the viewer
+    pub classes: Vec<ReplayClass>,
+    pub cars: Vec<ReplayCar>,
+    frames: Vec<CarFrame>, // tick by tick, cars in the same order as self.cars
+}
+
+impl ReplayClass {
+    pub fn load_class(&self) -> CarClass {
+        // start from the built in car and only change what the replay stored
+        let mut spec = CarSpec::gt();
+        spec.name = self.name.clone();
+        spec.sprite = self.sprite.clone();
+        spec.width = self.size.x;
+        spec.length = self.size.y;
+        spec.tint = self.tint;
+        return spec.load_class();
+    }
+}
+
+impl Replay {
+    pub fn start(config: &SimConfig, track_id: &str, generation: usize, cars: &[Car]) -> Self {
+        let classes: Vec<ReplayClass> = config
+            .car_classes
+            .iter()
+            .map(|spec| ReplayClass {
+                name: spec.name.clone(),
+                sprite: spec.sprite.clone(),
+                size: vec2(spec.width, spec.length),
+                tint: spec.tint,
+            })
+            .collect();
+
+        let cars = cars
+            .iter()
+            .map(|car| ReplayCar {
+                number: car.number,
+                class: classes
+                    .iter()
+                    .position(|c| c.name == car.spec.name)
+                    .unwrap_or(0),
+                genome: car.brain.to_text(),
+            })
+            .collect();
+
+        // the seed is stored on its own line
+        let settings = config
+            .describe()
+            .into_iter()
+            .filter(|(key, _)| key != "seed")
+            .collect();
+
+        Self {
+            track_id: track_id.to_string(),
+            seed: config.seed,
+            generation,
+            settings,
+            classes,
+            cars,
+            frames: vec![],
+        }
+    }
+
+    pub fn record_tick(&mut self, cars: &[Car]) {
+        for car in cars.iter() {
+            self.frames.push(CarFrame {
+                position: car.get_centre(),
+                angle: car.get_angle(),
+                crashed: car.crashed,
+            });
+        }
+    }
+
+    pub fn get_ticks(&self) -> usize {
+        if self.cars.is_empty() {
+            return 0;
+        }
+        return self.frames.len() / self.cars.len();
+    }
+
+    pub fn get_frames(&self, tick: usize) -> &[CarFrame] {
+        let start = tick * self.cars.len();
+        return &self.frames[start..start + self.cars.len()];
+    }
+
+    // the first tick a car was seen crashed, if it ever was
+    pub fn get_crash_tick(&self, car: usize) -> Option<usize> {
+        return (0..self.get_ticks()).find(|&tick| self.get_frames(tick)[car].crashed);
+    }
+
+    fn header(&self) -> String {
+        let mut text = format!(
+            "track {}\nseed {}\ngeneration {}\n",
+            self.track_id, self.seed, self.generation
+        );
+        for (key, value) in self.settings.iter() {
+            text += &format!("{} {}\n", key, value);
+        }
+        for class in self.classes.iter() {
+            let c: [u8; 4] = class.tint.into();
+            text += &format!(
+                "class {}|{}|{}|{}|{},{},{}\n",
+                class.name, class.sprite, class.size.x, class.size.y, c[0], c[1], c[2]
+            );
+        }
+        // each genome runs until the end line
+        for car in self.cars.iter() {
+            text += &format!("car {} {}\n{}end\n", car.number, car.class, car.genome);
+        }
+        return text;
+    }
+
+    fn to_bytes(&self) -> Vec<u8> {
+        let header = self.header();
+        let mut bytes: Vec<u8> = vec![];
+        bytes.extend_from_slice(MAGIC);
+        bytes.extend_from_slice(&(header.len() as u32).to_le_bytes());
+        bytes.extend_from_slice(header.as_bytes());
+        bytes.extend_from_slice(&(self.get_ticks() as u32).to_le_bytes());
+        bytes.extend_from_slice(&(self.cars.len() as u32).to_le_bytes());
+        for frame in self.frames.iter() {
+            bytes.extend_from_slice(&frame.position.x.to_le_bytes());
+            bytes.extend_from_slice(&frame.position.y.to_le_bytes());
+            bytes.extend_from_slice(&frame.angle.to_le_bytes());
+            bytes.push(frame.crashed as u8);
+        }
+        return bytes;
+    }
+
+    pub fn save(&self) {
+        let bytes = self.to_bytes();
+        let path = format!(
+            "{}/{}_gen{:04}.{}",
+            REPLAY_DIR, self.seed, self.generation, REPLAY_EXTENSION
+        );
+        let result = fs::create_dir_all(REPLAY_DIR).and_then(|_| fs::write(&path, bytes));
+        if let Err(e) = result {
+            println!("[replay.rs] - Couldn't save replay {}: {}", path, e);
+            return;
+        }
+
+        // throw away the oldest replays
+        let files = list_replays();
+        if files.len() > REPLAYS_KEPT {
+            for old in files[..files.len() - REPLAYS_KEPT].iter() {
+                let _ = fs::remove_file(old);
+            }
+        }
+    }
+
+    pub fn load(path: &Path) -> Option<Self> {
+        let bytes = fs::read(path).ok()?;
+        return Self::from_bytes(&bytes, &path.display().to_string());
+    }
+
+    fn from_bytes(bytes: &[u8], name: &str) -> Option<Self> {
+        let mut reader = Reader { bytes, at: 0 };
+
+        if reader.take(4)? != MAGIC {
+            println!("[replay.rs] - {} is not a replay", name);
+            return None;
+        }
+        let header_length = reader.u32()? as usize;
+        let header = std::str::from_utf8(reader.take(header_length)?).ok()?;
+
+        let mut replay = Self {
+            track_id: String::new(),
+            seed: 0,
+            generation: 0,
+            settings: vec![],
+            classes: vec![],
+            cars: vec![],
+            frames: vec![],
+        };
+        let mut lines = header.lines();
+        while let Some(line) = lines.next() {
+            let (key, value) = line.split_once(' ')?;
+            match key {
+                "track" => replay.track_id = value.to_string(),
+                "seed" => replay.seed = value.parse().ok()?,
+                "generation" => replay.generation = value.parse().ok()?,
+                "class" => {
+                    let parts: Vec<&str> = value.split('|').collect();
+                    let [name, sprite, width, length, tint] = parts[..] else {
+                        return None;
+                    };
+                    let rgb: Vec<u8> = tint
+                        .split(',')
+                        .map(|p| p.parse().ok())
+                        .collect::<Option<_>>()?;
+                    let [r, g, b] = rgb[..] else {
+                        return None;
+                    };
+                    replay.classes.push(ReplayClass {
+                        name: name.to_string(),
+                        sprite: sprite.to_string(),
+                        size: vec2(width.parse().ok()?, length.parse().ok()?),
+                        tint: Color::from_rgba(r, g, b, 255),
+                    });
+                }
+                "car" => {
+                    let (number, class) = value.split_once(' ')?;
+                    let mut genome = String::new();
+                    for layer in lines.by_ref() {
+                        if layer == "end" {
+                            break;
+                        }
+                        genome += layer;
+                        genome += "\n";
+                    }
+                    replay.cars.push(ReplayCar {
+                        number: number.parse().ok()?,
+                        class: class.parse().ok()?,
+                        genome,
+                    });
+                }
+                _ => replay.settings.push((key.to_string(), value.to_string())),
+            }
+        }
+
+        let ticks = reader.u32()? as usize;
+        let cars = reader.u32()? as usize;
+        if ticks == 0 || cars != replay.cars.len() || replay.classes.is_empty() {
+            return None;
+        }
+        // the viewer looks each car's class up by index
+        if replay
+            .cars
+            .iter()
+            .any(|car| car.class >= replay.classes.len())
+        {
+            println!("[replay.rs] - {} has a car with an unknown class", name);
+            return None;
+        }
+        for _ in 0..ticks * cars {
+            let position = vec2(reader.f32()?, reader.f32()?);
+            let angle = reader.f32()?;
+            let crashed = reader.take(1)?[0] != 0;
+            replay.frames.push(CarFrame {
+                position,
+                angle,
+                crashed,
+            });
+        }
+
+        return Some(replay);
+    }
+}
+
+// every saved replay, oldest first
+pub fn list_replays() -> Vec<PathBuf> {
+    let Ok(entries) = fs::read_dir(REPLAY_DIR) else {
+        return vec![];
+    };
+    let mut files: Vec<(std::time::SystemTime, PathBuf)> = entries
+        .filter_map(|e| e.ok())
+        .map(|e| e.path())
+        .filter(|p| p.extension().is_some_and(|ext| ext == REPLAY_EXTENSION))
+        .filter_map(|p| Some((fs::metadata(&p).ok()?.modified().ok()?, p)))
+        .collect();
+    files.sort();
+    return files.into_iter().map(|(_, p)| p).collect();
+}
+
+// reads little endian values from the front of a byte slice
+struct Reader<'a> {
+    bytes: &'a [u8],
+    at: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, count: usize) -> Option<&'a [u8]> {
+        let slice = self.bytes.get(self.at..self.at + count)?;
+        self.at += count;
+        return Some(slice);
+    }
+
+    fn u32(&mut self) -> Option<u32> {
+        return Some(u32::from_le_bytes(self.take(4)?.try_into().ok()?));
+    }
+
+    fn f32(&mut self) -> Option<f32> {
+        return Some(f32::from_le_bytes(self.take(4)?.try_into().ok()?));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn small_replay(car_class: usize) -> Replay {
+        Replay {
+            track_id: "test_track1".to_string(),
+            seed: 7,
+            generation: 3,
+            settings: vec![("time_limit".to_string(), "1250".to_string())],
+            classes: vec![ReplayClass {
+                name: "GT".to_string(),
+                sprite: "assets/car.png".to_string(),
+                size: vec2(20.0, 40.0),
+                tint: Color::from_rgba(200, 30, 30, 255),
+            }],
+            cars: vec![ReplayCar {
+                number: 1,
+                class: car_class,
+                genome: "none|0.5\n".to_string(),
+            }],
+            frames: vec![
+                CarFrame {
+                    position: vec2(1.0, 2.0),
+                    angle: 0.5,
+                    crashed: false,
+                },
+                CarFrame {
+                    position: vec2(3.0, 4.0),
+                    angle: 0.6,
+                    crashed: true,
+                },
+            ],
+        }
+    }
+
+    #[test]
+    fn saved_bytes_load_back() {
+        let replay = Replay::from_bytes(&small_replay(0).to_bytes(), "test").unwrap();
+        assert_eq!(replay.track_id, "test_track1");
+        assert_eq!(replay.generation, 3);
+        assert_eq!(replay.cars.len(), 1);
+        assert_eq!(replay.get_ticks(), 2);
+        assert_eq!(replay.get_crash_tick(0), Some(1));
+    }
+
+    #[test]
+    fn car_with_unknown_class_is_rejected() {
+        assert!(Replay::from_bytes(&small_replay(1).to_bytes(), "test").is_none());
+    }
+
+    #[test]
+    fn truncated_file_is_rejected() {
+        let bytes = small_replay(0).to_bytes();
+        assert!(Replay::from_bytes(&bytes[..bytes.len() - 5], "test").is_none());
+    }
+}
@@ -0,0 +1,283 @@
+use crate::camera::Camera;
+use crate::car_spec::CarClass;
+use crate::replay::*;
+use crate::track::Track;
+use macroquad::prelude::*;
+use std::f32::consts::PI;
+use std::path::{Path, PathBuf};
+
+const CRASHED_COLOUR: Color = color_u8!(255, 255, 255, 50);
+const FOLLOW_COLOUR: Color = color_u8!(255, 215, 0, 255);
+const TRAIL_COLOUR: Color = color_u8!(255, 215, 0, 160);
+const TRAIL_TICKS: usize = 150;
+const PLAYBACK_SPEEDS: [f32; 6] = [0.25, 0.5, 1.0, 2.0, 4.0, 8.0];
+const SCRUB_BAR_HEIGHT: f32 = 30.0;
+
+// plays back a saved generation
+// space pauses, left and right step (shift for 10), up and down change the speed,
+// clicking a car follows it, page up and page down change replay
+pub struct ReplayViewer {
+    files: Vec<PathBuf>,
+    file_index: usize,
+    replay: Replay,
+    track: Track,
+    classes: Vec<CarClass>,
+    tick: f32, // fractional so slow playback still moves
+    playing: bool,
+    speed: usize, // index into PLAYBACK_SPEEDS
+    following: Option<usize>,
+    scrubbing: bool,
+}
+
+impl ReplayViewer {
+    // opens the newest replay, None if there aren't any that can be read
+    pub fn new() -> Option<Self> {
+        let files = list_replays();
+        if files.is_empty() {
+            println!("[replay_viewer.rs] - No replays in {}", REPLAY_DIR);
+            return None;
+        }
+        let file_index = files.len() - 1;
+        let (replay, track, classes) = Self::open(&files[file_index])?;
+
+        Some(Self {
+            files,
+            file_index,
+            replay,
+            track,
+            classes,
+            tick: 0.0,
+            playing: true,
+            speed: 2,
+            following: None,
+            scrubbing: false,
+        })
+    }
+
+    fn open(path: &Path) -> Option<(Replay, Track, Vec<CarClass>)> {
+        let Some(replay) = Replay::load(path) else {
+            println!("[replay_viewer.rs] - Couldn't read {}", path.display());
+            return None;
+        };
+        let Some(track) = Track::from_id(&replay.track_id) else {
+            println!("[replay_viewer.rs] - Unknown track {}", replay.track_id);
+            return None;
+        };
+        let classes = replay.classes.iter().map(|c| c.load_class()).collect();
+        return Some((replay, track, classes));
+    }
+
+    fn change_file(&mut self, step: isize) {
+        let count = self.files.len() as isize;
+        let index = (self.file_index as isize + step).rem_euclid(count) as usize;
+        if let Some((replay, track, classes)) = Self::open(&self.files[index]) {
+            self.file_index = index;
+            self.replay = replay;
+            self.track = track;
+            self.classes = classes;
+            self.tick = 0.0;
+            self.following = None;
+        }
+    }
+
+    fn last_tick(&self) -> usize {
+        return self.replay.get_ticks().saturating_sub(1);
+    }
+
+    fn current_tick(&self) -> usize {
+        return (self.tick as usize).min(self.last_tick());
+    }
+
+    fn scrub_bar(&self) -> Rect {
+        return Rect::new(
+            0.0,
+            screen_height() - SCRUB_BAR_HEIGHT,
+            screen_width() - 160.0, // leaves room for the end button
+            SCRUB_BAR_HEIGHT,
+        );
+    }
+
+    pub fn get_track(&self) -> &Track {
+        return &self.track;
+    }
+
+    // where the followed car is, for the camera
+    pub fn get_followed_pos(&self) -> Option<Vec2> {
+        let i = self.following?;
+        return Some(self.replay.get_frames(self.current_tick())[i].position);
+    }
+
+    pub fn update(&mut self, camera: &Camera) {
+        if is_key_pressed(KeyCode::Space) {
+            self.playing = !self.playing;
+        }
+        if is_key_pressed(KeyCode::Up) {
+            self.speed = (self.speed + 1).min(PLAYBACK_SPEEDS.len() - 1);
+        }
+        if is_key_pressed(KeyCode::Down) {
+            self.speed = self.speed.saturating_sub(1);
+        }
+        if is_key_pressed(KeyCode::PageUp) {
+            self.change_file(-1);
+        }
+        if is_key_pressed(KeyCode::PageDown) {
+            self.change_file(1);
+        }
+
+        // stepping pauses playback so the frame can be looked at
+        let step = match is_key_down(KeyCode::LeftShift) {
+            true => 10.0,
+            false => 1.0,
+        };
+        if is_key_pressed(KeyCode::Right) {
+            self.playing = false;
+            self.tick = self.tick.floor() + step;
+        }
+        if is_key_pressed(KeyCode::Left) {
+            self.playing = false;
+            self.tick = (self.tick.floor() - step).max(0.0);
+        }
+
+        let mouse = Vec2::from(mouse_position());
+        let bar = self.scrub_bar();
+        if is_mouse_button_pressed(MouseButton::Left) {
+            if bar.contains(mouse) {
+                self.scrubbing = true;
+            } else if mouse.y < bar.y - SCRUB_BAR_HEIGHT {
+                self.following = self.car_at(camera.screen_to_world(mouse));
+            }
+        }
+        if is_mouse_button_released(MouseButton::Left) {
+            self.scrubbing = false;
+        }
+        if self.scrubbing {
+            let fraction = clamp((mouse.x - bar.x) / bar.w, 0.0, 1.0);
+            self.tick = (fraction * self.last_tick() as f32).round();
+        }
+
+        if self.playing && !self.scrubbing {
+            self.tick += PLAYBACK_SPEEDS[self.speed];
+        }
+        self.tick = self.tick.min(self.last_tick() as f32);
+    }
+
+    // the car under the mouse, skipping crashed cars so the live ones can be picked out
+    fn car_at(&self, point: Vec2) -> Option<usize> {
+        let frames = self.replay.get_frames(self.current_tick());
+        for (i, frame) in frames.iter().enumerate() {
+            let class = &self.classes[self.replay.cars[i].class];
+            let radius = class.spec.width.max(class.spec.length) / 2.0;
+            if !frame.crashed && frame.position.distance(point) <= radius {
+                return Some(i);
+            }
+        }
+        return None;
+    }
+
+    // drawn through the camera
+    pub fn draw_world(&self) {
+        self.track.draw();
+
+        let tick = self.current_tick();
+        let frames = self.replay.get_frames(tick);
+
+        // trail of where the followed car has been
+        if let Some(i) = self.following {
+            let start = tick.saturating_sub(TRAIL_TICKS);
+            for t in start..tick {
+                let a = self.replay.get_frames(t)[i].position;
+                let b = self.replay.get_frames(t + 1)[i].position;
+                draw_line(a.x, a.y, b.x, b.y, 3.0, TRAIL_COLOUR);
+            }
+        }
+
+        for (i, frame) in frames.iter().enumerate() {
+            let class = &self.classes[self.replay.cars[i].class];
+            let mut colour = class.spec.tint;
+            if frame.crashed {
+                colour = CRASHED_COLOUR;
+            }
+            if self.following == Some(i) {
+                colour = FOLLOW_COLOUR;
+            }
+
+            let size = vec2(class.spec.width, class.spec.length);
+            let params = DrawTextureParams {
+                dest_size: Some(size),
+                rotation: frame.angle + PI / 2.0,
+                ..Default::default()
+            };
+            let corner = frame.position - size / 2.0;
+            if let Some(texture) = &class.texture {
+                draw_texture_ex(texture, corner.x, corner.y, colour, params);
+            }
+        }
+    }
+
+    // drawn in screen pixels on top of the world
+    pub fn draw_hud(&self) {
+        let tick = self.current_tick();
+        self.draw_info(tick);
+        self.draw_scrub_bar(tick);
+    }
+
+    fn draw_info(&self, tick: usize) {
+        let file = self.files[self.file_index]
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_default();
+        let state = match self.playing {
+            true => "playing",
+            false => "paused",
+        };
+        let lines = [
+            format!(
+                "Replay {} ({}/{})",
+                file,
+                self.file_index + 1,
+                self.files.len()
+            ),
+            format!(
+                "Generation {}  seed {}  track {}",
+                self.replay.generation, self.replay.seed, self.replay.track_id
+            ),
+            format!(
+                "Tick {} / {}  {} x{}",
+                tick,
+                self.last_tick(),
+                state,
+                PLAYBACK_SPEEDS[self.speed]
+            ),
+        ];
+        for (i, line) in lines.iter().enumerate() {
+            draw_text(line, 20.0, 30.0 + i as f32 * 25.0, 25.0, BLACK);
+        }
+
+        if let Some(i) = self.following {
+            let car = &self.replay.cars[i];
+            let crash_text = match self.replay.get_crash_tick(i) {
+                Some(crash_tick) => format!("crashed on tick {}", crash_tick),
+                None => "never crashed".to_string(),
+            };
+            let text = format!(
+                "Following car {} ({}), {}",
+                car.number, self.classes[car.class].spec.name, crash_text
+            );
+            draw_text(&text, 20.0, 110.0, 25.0, BLACK);
+        }
+    }
+
+    fn draw_scrub_bar(&self, tick: usize) {
+        let bar = self.scrub_bar();
+        let fraction = tick as f32 / self.last_tick().max(1) as f32;
+        draw_rectangle(bar.x, bar.y, bar.w, bar.h, LIGHTGRAY);
+        draw_rectangle(bar.x, bar.y, bar.w * fraction, bar.h, YELLOW);
+        draw_rectangle_lines(bar.x, bar.y, bar.w, bar.h, 2.0, BLACK);
+
+        // mark when the followed car crashed
+        if let Some(crash_tick) = self.following.and_then(|i| self.replay.get_crash_tick(i)) {
+            let x = bar.x + bar.w * crash_tick as f32 / self.last_tick().max(1) as f32;
+            draw_line(x, bar.y, x, bar.y + bar.h, 3.0, RED);
+        }
+    }
+}
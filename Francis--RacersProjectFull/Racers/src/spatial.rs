@@ -0,0 +1,410 @@
+use crate::geometry::{closest_point_on_segment, ray_segment_intersection};
+use macroquad::prelude::*;
+
+// uniform grid over a set of line segments
+// each cell stores the indices of every segment whose bounding box overlaps it,
+// so a ray only has to test the segments in the cells it actually passes through
+pub struct SegmentGrid {
+    segments: Vec<(Vec2, Vec2)>,
+    cells: Vec<Vec<usize>>,
+    origin: Vec2, // top left corner of the grid
+    cell_size: f32,
+    cols: usize,
+    rows: usize,
+}
+
+impl SegmentGrid {
+    pub fn new(segments: Vec<(Vec2, Vec2)>, cell_size: f32) -> Self {
+        // find the bounds of all the segments
+        let mut min = Vec2::splat(f32::MAX);
+        let mut max = Vec2::splat(f32::MIN);
+        for (a, b) in segments.iter() {
+            min = min.min(a.min(*b));
+            max = max.max(a.max(*b));
+        }
+        if segments.is_empty() {
+            min = Vec2::ZERO;
+            max = Vec2::ZERO;
+        }
+
+        // pad by one cell so points on the edge still land inside the grid
+        let origin = min - Vec2::splat(cell_size);
+        let size = (max + Vec2::splat(cell_size)) - origin;
+        let cols = (size.x / cell_size).ceil().max(1.0) as usize;
+        let rows = (size.y / cell_size).ceil().max(1.0) as usize;
+
+        let mut grid = Self {
+            segments,
+            cells: vec![vec![]; cols * rows],
+            origin,
+            cell_size,
+            cols,
+            rows,
+        };
+
+        // place each segment in every cell its bounding box touches
+        for i in 0..grid.segments.len() {
+            let (a, b) = grid.segments[i];
+            let (c1, r1) = grid.cell_of(a.min(b));
+            let (c2, r2) = grid.cell_of(a.max(b));
+            for row in r1..=r2 {
+                for col in c1..=c2 {
+                    grid.cells[row * cols + col].push(i);
+                }
+            }
+        }
+
+        grid
+    }
+
+    pub fn get_segments(&self) -> &[(Vec2, Vec2)] {
+        &self.segments
+    }
+
+    fn cell_of(&self, point: Vec2) -> (usize, usize) {
+        let local = (point - self.origin) / self.cell_size;
+        let col = clamp(local.x.floor() as i32, 0, self.cols as i32 - 1) as usize;
+        let row = clamp(local.y.floor() as i32, 0, self.rows as i32 - 1) as usize;
+        (col, row)
+    }
+
+    // returns the closest point on any segment and that segment's index
+    // searches square rings of cells outwards from the point's cell until nothing
+    // outside the searched area could be closer
+    pub fn nearest(&self, point: Vec2) -> Option<(Vec2, usize)> {
+        let (col, row) = self.cell_of(point);
+        let mut best: Option<(f32, Vec2, usize)> = None;
+
+        for ring in 0..=self.cols.max(self.rows) {
+            let (c1, c2) = (col.saturating_sub(ring), (col + ring).min(self.cols - 1));
+            let (r1, r2) = (row.saturating_sub(ring), (row + ring).min(self.rows - 1));
+            for r in r1..=r2 {
+                for c in c1..=c2 {
+                    // the inner cells were searched by the smaller rings
+                    let ring_of_cell = c.abs_diff(col).max(r.abs_diff(row));
+                    if ring_of_cell != ring {
+                        continue;
+                    }
+                    for &i in self.cells[r * self.cols + c].iter() {
+                        let (a, b) = self.segments[i];
+                        let closest = closest_point_on_segment(point, a, b);
+                        let distance = closest.distance(point);
+                        if best.is_none_or(|(d, _, _)| distance < d) {
+                            best = Some((distance, closest, i));
+                        }
+                    }
+                }
+            }
+
+            // how far the point is from the edge of the searched area, sides on the
+            // edge of the grid don't count as nothing lies beyond them
+            // a point outside the grid gets a negative clearance and searches it all
+            let low = self.origin + vec2(c1 as f32, r1 as f32) * self.cell_size;
+            let high = self.origin + vec2(c2 as f32 + 1.0, r2 as f32 + 1.0) * self.cell_size;
+            let mut clearance = f32::MAX;
+            if c1 > 0 {
+                clearance = clearance.min(point.x - low.x);
+            }
+            if c2 < self.cols - 1 {
+                clearance = clearance.min(high.x - point.x);
+            }
+            if r1 > 0 {
+                clearance = clearance.min(point.y - low.y);
+            }
+            if r2 < self.rows - 1 {
+                clearance = clearance.min(high.y - point.y);
+            }
+            if clearance == f32::MAX || best.is_some_and(|(d, _, _)| d <= clearance) {
+                break;
+            }
+        }
+        best.map(|(_, closest, i)| (closest, i))
+    }
+
+    // returns the distance along the ray to the closest segment and that segment's index
+    // the direction must be normalised
+    pub fn cast_ray(
+        &self,
+        origin: Vec2,
+        direction: Vec2,
+        max_distance: f32,
+    ) -> Option<(f32, usize)> {
+        let grid_size = vec2(self.cols as f32, self.rows as f32) * self.cell_size;
+
+        // clip the ray against the grid bounds (slab test)
+        let mut t_enter: f32 = 0.0;
+        let mut t_leave: f32 = max_distance;
+        for axis in 0..2 {
+            let o = origin[axis] - self.origin[axis];
+            let d = direction[axis];
+            if d.abs() < f32::EPSILON {
+                if o < 0.0 || o > grid_size[axis] {
+                    return None;
+                }
+            } else {
+                let t1 = -o / d;
+                let t2 = (grid_size[axis] - o) / d;
+                t_enter = t_enter.max(t1.min(t2));
+                t_leave = t_leave.min(t1.max(t2));
+            }
+        }
+        if t_enter > t_leave {
+            return None;
+        }
+
+        // walk the cells along the ray (Amanatides & Woo traversal)
+        let start = origin + direction * t_enter;
+        let (mut col, mut row) = self.cell_of(start);
+        let step_col: i32 = if direction.x >= 0.0 { 1 } else { -1 };
+        let step_row: i32 = if direction.y >= 0.0 { 1 } else { -1 };
+
+        // distance along the ray to the next cell boundary on each axis
+        let next_boundary = |cell: usize, step: i32, axis: usize| -> f32 {
+            let edge = self.origin[axis] + (cell as i32 + step.max(0)) as f32 * self.cell_size;
+            if direction[axis].abs() < f32::EPSILON {
+                f32::MAX
+            } else {
+                (edge - origin[axis]) / direction[axis]
+            }
+        };
+        let mut t_max_x = next_boundary(col, step_col, 0);
+        let mut t_max_y = next_boundary(row, step_row, 1);
+        let t_delta_x = if direction.x.abs() < f32::EPSILON {
+            f32::MAX
+        } else {
+            self.cell_size / direction.x.abs()
+        };
+        let t_delta_y = if direction.y.abs() < f32::EPSILON {
+            f32::MAX
+        } else {
+            self.cell_size / direction.y.abs()
+        };
+
+        let mut best: Option<(f32, usize)> = None;
+        loop {
+            for &i in self.cells[row * self.cols + col].iter() {
+                let (a, b) = self.segments[i];
+                if let Some(t) = ray_segment_intersection(origin, direction, a, b) {
+                    if t <= max_distance && best.is_none_or(|(bt, _)| t < bt) {
+                        best = Some((t, i));
+                    }
+                }
+            }
+
+            // a hit inside this cell can't be beaten by anything further along
+            let t_cell_exit = t_max_x.min(t_max_y);
+            if let Some((t, _)) = best {
+                if t <= t_cell_exit {
+                    return best;
+                }
+            }
+            if t_cell_exit > t_leave {
+                return best;
+            }
+
+            // step into the next cell
+            if t_max_x < t_max_y {
+                let next = col as i32 + step_col;
+                if next < 0 || next >= self.cols as i32 {
+                    return best;
+                }
+                col = next as usize;
+                t_max_x += t_delta_x;
+            } else {
+                let next = row as i32 + step_row;
+                if next < 0 || next >= self.rows as i32 {
+                    return best;
+                }
+                row = next as usize;
+                t_max_y += t_delta_y;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // small xorshift generator so the property tests are repeatable
+    struct Rng(u64);
+
+    impl Rng {
+        fn next_f32(&mut self) -> f32 {
+            self.0 ^= self.0 << 13;
+            self.0 ^= self.0 >> 7;
+            self.0 ^= self.0 << 17;
+            (self.0 >> 40) as f32 / (1u64 << 24) as f32
+        }
+
+        fn range(&mut self, min: f32, max: f32) -> f32 {
+            min + (max - min) * self.next_f32()
+        }
+
+        fn point(&mut self, min: f32, max: f32) -> Vec2 {
+            vec2(self.range(min, max), self.range(min, max))
+        }
+
+        fn direction(&mut self) -> Vec2 {
+            let angle = self.range(0.0, std::f32::consts::TAU);
+            vec2(angle.cos(), angle.sin())
+        }
+    }
+
+    const CASES: usize = 3000;
+    const CELL_SIZE: f32 = 50.0;
+
+    // the answer the grid should give, found by testing every segment
+    fn brute_force(
+        segments: &[(Vec2, Vec2)],
+        origin: Vec2,
+        direction: Vec2,
+        max_distance: f32,
+    ) -> Option<f32> {
+        let mut best: Option<f32> = None;
+        for (a, b) in segments.iter() {
+            if let Some(t) = ray_segment_intersection(origin, direction, *a, *b) {
+                if t <= max_distance && best.is_none_or(|bt| t < bt) {
+                    best = Some(t);
+                }
+            }
+        }
+        best
+    }
+
+    fn assert_matches(grid: &SegmentGrid, origin: Vec2, direction: Vec2, max_distance: f32) {
+        let expected = brute_force(grid.get_segments(), origin, direction, max_distance);
+        let found = grid
+            .cast_ray(origin, direction, max_distance)
+            .map(|(t, _)| t);
+        match (expected, found) {
+            (None, None) => {}
+            (Some(e), Some(f)) => assert!(
+                (e - f).abs() < 1e-3,
+                "ray from {} along {}: grid {} brute force {}",
+                origin,
+                direction,
+                f,
+                e
+            ),
+            _ => panic!(
+                "ray from {} along {}: grid {:?} brute force {:?}",
+                origin, direction, found, expected
+            ),
+        }
+    }
+
+    fn random_grid(rng: &mut Rng) -> SegmentGrid {
+        let mut segments = vec![];
+        for _ in 0..40 {
+            let a = rng.point(0.0, 1000.0);
+            segments.push((a, a + rng.point(-150.0, 150.0)));
+        }
+        SegmentGrid::new(segments, CELL_SIZE)
+    }
+
+    #[test]
+    fn rays_inside_the_grid_match_brute_force() {
+        let mut rng = Rng(0x2545F4914F6CDD1D);
+        let grid = random_grid(&mut rng);
+        for _ in 0..CASES {
+            let origin = rng.point(0.0, 1000.0);
+            let direction = rng.direction();
+            assert_matches(&grid, origin, direction, rng.range(10.0, 2000.0));
+        }
+    }
+
+    #[test]
+    fn rays_starting_outside_the_grid_match_brute_force() {
+        let mut rng = Rng(0x9E3779B97F4A7C15);
+        let grid = random_grid(&mut rng);
+        for _ in 0..CASES {
+            // beyond the padded grid on at least one side
+            let mut origin = rng.point(-800.0, 1800.0);
+            if rng.next_f32() < 0.5 {
+                origin.x = match rng.next_f32() < 0.5 {
+                    true => -500.0,
+                    false => 1500.0,
+                };
+            }
+            let direction = rng.direction();
+            assert_matches(&grid, origin, direction, 4000.0);
+        }
+    }
+
+    #[test]
+    fn rays_along_cell_edges_match_brute_force() {
+        let mut rng = Rng(0xD1B54A32D192ED03);
+        let grid = random_grid(&mut rng);
+        let directions = [
+            vec2(1.0, 0.0),
+            vec2(-1.0, 0.0),
+            vec2(0.0, 1.0),
+            vec2(0.0, -1.0),
+        ];
+        for i in 0..=(grid.cols.max(grid.rows)) {
+            let line = i as f32 * CELL_SIZE;
+            for direction in directions {
+                // on a vertical and a horizontal grid line, from inside and outside
+                let along = rng.range(-200.0, 1200.0);
+                let on_column = grid.origin + vec2(line, along);
+                let on_row = grid.origin + vec2(along, line);
+                assert_matches(&grid, on_column, direction, 3000.0);
+                assert_matches(&grid, on_row, direction, 3000.0);
+            }
+            // diagonally through the cell corners
+            let corner = grid.origin + Vec2::splat(line);
+            assert_matches(&grid, corner, vec2(1.0, 1.0).normalize(), 3000.0);
+            assert_matches(&grid, corner, vec2(-1.0, 1.0).normalize(), 3000.0);
+        }
+    }
+
+    #[test]
+    fn nearest_matches_brute_force() {
+        let mut rng = Rng(0xA0761D6478BD642F);
+        let grid = random_grid(&mut rng);
+        for _ in 0..CASES {
+            // some points well outside the grid
+            let point = rng.point(-600.0, 1600.0);
+            let expected = grid
+                .get_segments()
+                .iter()
+                .map(|(a, b)| closest_point_on_segment(point, *a, *b).distance(point))
+                .fold(f32::MAX, f32::min);
+            let (closest, index) = grid.nearest(point).unwrap();
+            let (a, b) = grid.get_segments()[index];
+            assert!((closest.distance(point) - expected).abs() < 1e-3);
+            assert!(closest_point_on_segment(closest, a, b).distance(closest) < 1e-3);
+        }
+    }
+
+    #[test]
+    fn nearest_of_no_segments_is_none() {
+        let grid = SegmentGrid::new(vec![], CELL_SIZE);
+        assert!(grid.nearest(vec2(10.0, 10.0)).is_none());
+    }
+
+    #[test]
+    fn ray_pointing_away_from_the_grid_misses() {
+        let grid = SegmentGrid::new(vec![(vec2(0.0, 0.0), vec2(100.0, 0.0))], CELL_SIZE);
+        assert!(grid
+            .cast_ray(vec2(50.0, -500.0), vec2(0.0, -1.0), 1000.0)
+            .is_none());
+        assert!(grid
+            .cast_ray(vec2(50.0, -500.0), vec2(0.0, 1.0), 1000.0)
+            .is_some());
+    }
+
+    #[test]
+    fn hits_beyond_the_max_distance_are_ignored() {
+        let grid = SegmentGrid::new(vec![(vec2(0.0, 100.0), vec2(100.0, 100.0))], CELL_SIZE);
+        assert!(grid
+            .cast_ray(vec2(50.0, 0.0), vec2(0.0, 1.0), 99.0)
+            .is_none());
+        let (t, index) = grid
+            .cast_ray(vec2(50.0, 0.0), vec2(0.0, 1.0), 101.0)
+            .unwrap();
+        assert!((t - 100.0).abs() < 1e-4);
+        assert_eq!(index, 0);
+    }
+}
@@ -0,0 +1,325 @@
+use crate::car::Car;
+use crate::config::SimConfig;
+use crate::utils::format_date_time;
+use std::fs;
+use std::fs::File;
+use std::io::prelude::*;
+
+pub const RUNS_DIR: &str = "runs";
+const STATS_HEADER: &str =
+    "generation,best,mean,median,worst,std_dev,crashed,lapped,best_lap,mean_distance,diversity";
+
+// how one generation did, the fitness values are final fitnesses
+pub struct GenerationStats {
+    pub generation: usize,
+    pub best: i32,
+    pub mean: f64,
+    pub median: f64,
+    pub worst: i32,
+    pub std_dev: f64,
+    pub crashed: usize,
+    pub lapped: usize,         // cars that finished at least one lap
+    pub best_lap: Option<f32>, // fastest valid lap in seconds
+    pub mean_distance: f32,    // pixels driven per car
+    pub diversity: f64,        // spread of the genomes, 0 when every car is the same
+}
+
+// a folder per run holding the config and the stats of every generation
+pub struct RunLog {
+    pub dir: String,
+    stats_file: Option<File>,
+}
+
+impl GenerationStats {
+    pub fn from_cars(generation: usize, cars: &[Car], ticks: u32) -> Self {
+        let fitnesses: Vec<i32> = cars.iter().map(|c| c.get_final_fitness(ticks)).collect();
+        let genomes: Vec<Vec<f64>> = cars.iter().map(|c| c.brain.genome()).collect();
+        let count = cars.len().max(1) as f32;
+
+        let best_lap = cars
+            .iter()
+            .filter_map(|c| c.best_lap_seconds)
+            .min_by(|a, b| a.total_cmp(b));
+
+        Self {
+            crashed: cars.iter().filter(|c| c.crashed).count(),
+            lapped: cars.iter().filter(|c| c.laps > 0).count(),
+            best_lap,
+            mean_distance: cars.iter().map(|c| c.distance).sum::<f32>() / count,
+            diversity: diversity(&genomes),
+            ..Self::from_fitnesses(generation, fitnesses)
+        }
+    }
+
+    // the fitness columns on their own, the rest are left at zero
+    fn from_fitnesses(generation: usize, mut fitnesses: Vec<i32>) -> Self {
+        fitnesses.sort();
+        let count = fitnesses.len().max(1) as f64;
+
+        let mean = fitnesses.iter().map(|f| *f as f64).sum::<f64>() / count;
+        let variance = fitnesses
+            .iter()
+            .map(|f| (*f as f64 - mean).powi(2))
+            .sum::<f64>()
+            / count;
+        let median = match fitnesses.len() {
+            0 => 0.0,
+            n if n % 2 == 0 => (fitnesses[n / 2 - 1] as f64 + fitnesses[n / 2] as f64) / 2.0,
+            n => fitnesses[n / 2] as f64,
+        };
+
+        Self {
+            generation,
+            best: fitnesses.last().copied().unwrap_or(0),
+            mean,
+            median,
+            worst: fitnesses.first().copied().unwrap_or(0),
+            std_dev: variance.sqrt(),
+            crashed: 0,
+            lapped: 0,
+            best_lap: None,
+            mean_distance: 0.0,
+            diversity: 0.0,
+        }
+    }
+
+    pub fn to_csv_row(&self) -> String {
+        let best_lap = match self.best_lap {
+            Some(time) => format!("{:.3}", time),
+            None => String::new(),
+        };
+        return format!(
+            "{},{},{:.2},{:.2},{},{:.2},{},{},{},{:.1},{:.5}",
+            self.generation,
+            self.best,
+            self.mean,
+            self.median,
+            self.worst,
+            self.std_dev,
+            self.crashed,
+            self.lapped,
+            best_lap,
+            self.mean_distance,
+            self.diversity
+        );
+    }
+
+    // reads a row written by to_csv_row
+    // the old fitness files only have generation,best so the rest is left at zero
+    pub fn from_csv_row(line: &str) -> Option<Self> {
+        let parts: Vec<&str> = line.trim().split(',').collect();
+        let generation = parts.first()?.parse().ok()?;
+        let best = parts.get(1)?.parse().ok()?;
+        if parts.len() == 2 {
+            return Some(Self {
+                best,
+                ..Self::from_fitnesses(generation, vec![])
+            });
+        }
+        let [_, _, mean, median, worst, std_dev, crashed, lapped, best_lap, mean_distance, diversity] =
+            parts[..]
+        else {
+            return None;
+        };
+        Some(Self {
+            generation,
+            best,
+            mean: mean.parse().ok()?,
+            median: median.parse().ok()?,
+            worst: worst.parse().ok()?,
+            std_dev: std_dev.parse().ok()?,
+            crashed: crashed.parse().ok()?,
+            lapped: lapped.parse().ok()?,
+            best_lap: best_lap.parse().ok(), // empty when nobody lapped
+            mean_distance: mean_distance.parse().ok()?,
+            diversity: diversity.parse().ok()?,
+        })
+    }
+}
+
+// the standard deviation of each weight and bias across the cars, averaged over the genome
+pub fn diversity(genomes: &[Vec<f64>]) -> f64 {
+    let Some(length) = genomes.first().map(|g| g.len()) else {
+        return 0.0;
+    };
+    if length == 0 || genomes.iter().any(|g| g.len() != length) {
+        return 0.0;
+    }
+
+    let count = genomes.len() as f64;
+    let mut total = 0.0;
+    for gene in 0..length {
+        let mean = genomes.iter().map(|g| g[gene]).sum::<f64>() / count;
+        let variance = genomes
+            .iter()
+            .map(|g| (g[gene] - mean).powi(2))
+            .sum::<f64>()
+            / count;
+        total += variance.sqrt();
+    }
+    return total / length as f64;
+}
+
+impl RunLog {
+    pub fn create(config: &SimConfig, track_id: &str) -> Self {
+        let now = macroquad::miniquad::date::now() as u64;
+        let dir = format!("{}/{}_{}", RUNS_DIR, format_date_time(now), config.seed);
+
+        let mut manifest = format!(
+            "started = {}\ntrack = {}\n",
+            format_date_time(now),
+            track_id
+        );
+        for (key, value) in config.describe() {
+            manifest += &format!("{} = {}\n", key, value);
+        }
+
+        let result = fs::create_dir_all(&dir)
+            .and_then(|_| fs::write(format!("{}/manifest.txt", dir), manifest))
+            .and_then(|_| {
+                let mut file = File::create(format!("{}/generations.csv", dir))?;
+                writeln!(file, "{}", STATS_HEADER)?;
+                Ok(file)
+            });
+
+        let stats_file = match result {
+            Ok(file) => Some(file),
+            Err(e) => {
+                println!("[stats.rs] - Couldn't create run folder {}: {}", dir, e);
+                None
+            }
+        };
+        return Self { dir, stats_file };
+    }
+
+    pub fn write(&mut self, stats: &GenerationStats) {
+        if let Some(file) = &mut self.stats_file {
+            if let Err(e) = writeln!(file, "{}", stats.to_csv_row()) {
+                println!("[stats.rs] - Couldn't write stats to {}: {}", self.dir, e);
+            }
+        }
+    }
+}
+
+// every row of a stats file, skipping the header, None if the file can't be read
+pub fn load_history(path: &str) -> Option<Vec<GenerationStats>> {
+    let text = fs::read_to_string(path).ok()?;
+    let mut history = vec![];
+    for line in text.lines() {
+        if line.starts_with("generation") || line.trim().is_empty() {
+            continue;
+        }
+        match GenerationStats::from_csv_row(line) {
+            Some(stats) => history.push(stats),
+            None => println!("[stats.rs] - Bad row in {}: {}", path, line),
+        }
+    }
+    return Some(history);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn close(a: f64, b: f64) -> bool {
+        return (a - b).abs() < 1e-9;
+    }
+
+    #[test]
+    fn odd_count_median_is_the_middle() {
+        let stats = GenerationStats::from_fitnesses(3, vec![50, 10, 30]);
+        assert_eq!(stats.generation, 3);
+        assert_eq!(stats.best, 50);
+        assert_eq!(stats.worst, 10);
+        assert!(close(stats.mean, 30.0));
+        assert!(close(stats.median, 30.0));
+    }
+
+    #[test]
+    fn even_count_median_is_between_the_middle_two() {
+        let stats = GenerationStats::from_fitnesses(0, vec![4, 1, 10, 2]);
+        assert!(close(stats.median, 3.0));
+        assert!(close(stats.mean, 4.25));
+    }
+
+    #[test]
+    fn std_dev_is_the_population_one() {
+        // the usual example, mean 5 and every square off by a total of 32 over 8 values
+        let stats = GenerationStats::from_fitnesses(0, vec![2, 4, 4, 4, 5, 5, 7, 9]);
+        assert!(close(stats.mean, 5.0));
+        assert!(close(stats.std_dev, 2.0));
+
+        let same = GenerationStats::from_fitnesses(0, vec![7, 7, 7]);
+        assert!(close(same.std_dev, 0.0));
+    }
+
+    #[test]
+    fn no_cars_gives_zeros() {
+        let stats = GenerationStats::from_fitnesses(0, vec![]);
+        assert_eq!((stats.best, stats.worst), (0, 0));
+        assert!(close(stats.mean, 0.0));
+        assert!(close(stats.median, 0.0));
+        assert!(close(stats.std_dev, 0.0));
+    }
+
+    #[test]
+    fn diversity_averages_the_spread_of_each_gene() {
+        // the first gene is spread by 1 either side, the second not at all
+        let genomes = vec![vec![0.0, 3.0], vec![2.0, 3.0]];
+        assert!(close(diversity(&genomes), 0.5));
+
+        let clones = vec![vec![0.1, 0.2, 0.3]; 5];
+        assert!(close(diversity(&clones), 0.0));
+    }
+
+    #[test]
+    fn diversity_of_mismatched_or_missing_genomes_is_zero() {
+        assert!(close(diversity(&[]), 0.0));
+        assert!(close(diversity(&[vec![], vec![]]), 0.0));
+        assert!(close(diversity(&[vec![1.0, 2.0], vec![1.0]]), 0.0));
+    }
+
+    #[test]
+    fn csv_row_loads_back() {
+        let stats = GenerationStats {
+            crashed: 12,
+            lapped: 3,
+            best_lap: Some(41.25),
+            mean_distance: 1234.5,
+            diversity: 0.12345,
+            ..GenerationStats::from_fitnesses(7, vec![5, 10, 20, 40])
+        };
+        let row = stats.to_csv_row();
+        let loaded = GenerationStats::from_csv_row(&row).unwrap();
+        assert_eq!(loaded.to_csv_row(), row);
+        assert_eq!(loaded.generation, 7);
+        assert_eq!((loaded.best, loaded.worst), (40, 5));
+        assert!(close(loaded.median, 15.0));
+        assert_eq!((loaded.crashed, loaded.lapped), (12, 3));
+        assert_eq!(loaded.best_lap, Some(41.25));
+    }
+
+    #[test]
+    fn csv_row_without_a_lap_loads_back() {
+        let stats = GenerationStats::from_fitnesses(2, vec![1, 2, 3]);
+        let row = stats.to_csv_row();
+        let loaded = GenerationStats::from_csv_row(&row).unwrap();
+        assert_eq!(loaded.best_lap, None);
+        assert_eq!(loaded.to_csv_row(), row);
+    }
+
+    #[test]
+    fn old_two_column_rows_load() {
+        let loaded = GenerationStats::from_csv_row("4,950\n").unwrap();
+        assert_eq!((loaded.generation, loaded.best), (4, 950));
+        assert!(close(loaded.mean, 0.0));
+    }
+
+    #[test]
+    fn bad_rows_are_rejected() {
+        assert!(GenerationStats::from_csv_row("").is_none());
+        assert!(GenerationStats::from_csv_row("gen,best").is_none());
+        assert!(GenerationStats::from_csv_row("1,2,3").is_none());
+        assert!(GenerationStats::from_csv_row("1,2,x,4,5,6,7,8,,10,11").is_none());
+    }
+}
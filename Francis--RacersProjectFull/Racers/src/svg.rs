@@ -0,0 +1,355 @@
+use crate::graph::{ChartScale, BEST_COLOUR, LAP_COLOUR, MEAN_COLOUR, WORST_COLOUR};
+use crate::stats::GenerationStats;
+use crate::track::*;
+use macroquad::prelude::*;
+use std::fs;
+
+pub const REPORT_NAME: &str = "report.svg";
+
+const REPORT_WIDTH: f32 = 1200.0;
+const REPORT_HEIGHT: f32 = 640.0;
+const TITLE_HEIGHT: f32 = 40.0;
+
+// builds an svg image as text, so plots can be made without drawing to the screen
+// nothing in here touches the macroquad context, only its maths and colour types
+pub struct Svg {
+    width: f32,
+    height: f32,
+    body: String,
+}
+
+impl Svg {
+    pub fn new(width: f32, height: f32) -> Self {
+        let mut svg = Self {
+            width,
+            height,
+            body: String::new(),
+        };
+        svg.rect(Rect::new(0.0, 0.0, width, height), WHITE);
+        return svg;
+    }
+
+    pub fn rect(&mut self, area: Rect, fill: Color) {
+        self.body += &format!(
+            "<rect x=\"{:.1}\" y=\"{:.1}\" width=\"{:.1}\" height=\"{:.1}\" {}/>\n",
+            area.x,
+            area.y,
+            area.w,
+            area.h,
+            paint("fill", fill)
+        );
+    }
+
+    pub fn outline(&mut self, area: Rect, width: f32, colour: Color) {
+        self.body += &format!(
+            "<rect x=\"{:.1}\" y=\"{:.1}\" width=\"{:.1}\" height=\"{:.1}\" fill=\"none\" stroke-width=\"{}\" {}/>\n",
+            area.x,
+            area.y,
+            area.w,
+            area.h,
+            width,
+            paint("stroke", colour)
+        );
+    }
+
+    pub fn line(&mut self, a: Vec2, b: Vec2, width: f32, colour: Color) {
+        self.body += &format!(
+            "<line x1=\"{:.1}\" y1=\"{:.1}\" x2=\"{:.1}\" y2=\"{:.1}\" stroke-width=\"{}\" {}/>\n",
+            a.x,
+            a.y,
+            b.x,
+            b.y,
+            width,
+            paint("stroke", colour)
+        );
+    }
+
+    // a closed path joins the last point back to the first
+    pub fn path(&mut self, points: &[Vec2], width: f32, colour: Color, closed: bool) {
+        if points.is_empty() {
+            return;
+        }
+        let coords: Vec<String> = points
+            .iter()
+            .map(|p| format!("{:.1},{:.1}", p.x, p.y))
+            .collect();
+        let tag = match closed {
+            true => "polygon",
+            false => "polyline",
+        };
+        self.body += &format!(
+            "<{} points=\"{}\" fill=\"none\" stroke-width=\"{}\" stroke-linecap=\"round\" stroke-linejoin=\"round\" {}/>\n",
+            tag,
+            coords.join(" "),
+            width,
+            paint("stroke", colour)
+        );
+    }
+
+    pub fn circle(&mut self, centre: Vec2, radius: f32, colour: Color) {
+        self.body += &format!(
+            "<circle cx=\"{:.1}\" cy=\"{:.1}\" r=\"{}\" {}/>\n",
+            centre.x,
+            centre.y,
+            radius,
+            paint("fill", colour)
+        );
+    }
+
+    pub fn text(&mut self, position: Vec2, size: f32, text: &str, colour: Color) {
+        let text = text
+            .replace('&', "&amp;")
+            .replace('<', "&lt;")
+            .replace('>', "&gt;");
+        self.body += &format!(
+            "<text x=\"{:.1}\" y=\"{:.1}\" font-family=\"sans-serif\" font-size=\"{}\" {}>{}</text>\n",
+            position.x,
+            position.y,
+            size,
+            paint("fill", colour),
+            text
+        );
+    }
+
+    // everything until end_group is drawn at offset + point * scale
+    pub fn begin_group(&mut self, offset: Vec2, scale: f32) {
+        self.body += &format!(
+            "<g transform=\"translate({:.1} {:.1}) scale({:.4})\">\n",
+            offset.x, offset.y, scale
+        );
+    }
+
+    pub fn end_group(&mut self) {
+        self.body += "</g>\n";
+    }
+
+    pub fn to_text(&self) -> String {
+        return format!(
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{}\" height=\"{}\" viewBox=\"0 0 {} {}\">\n{}</svg>\n",
+            self.width, self.height, self.width, self.height, self.body
+        );
+    }
+
+    pub fn save(&self, path: &str) {
+        if let Err(e) = fs::write(path, self.to_text()) {
+            println!("[svg.rs] - Couldn't save {}: {}", path, e);
+        }
+    }
+}
+
+// e.g. fill="rgb(255,0,0)" fill-opacity="0.5"
+fn paint(attribute: &str, colour: Color) -> String {
+    let c: [u8; 4] = colour.into();
+    let mut text = format!("{}=\"rgb({},{},{})\"", attribute, c[0], c[1], c[2]);
+    if c[3] < 255 {
+        text += &format!(" {}-opacity=\"{:.2}\"", attribute, colour.a);
+    }
+    return text;
+}
+
+// the track scaled to fit the area, with any number of lines driven on it
+pub fn draw_track(svg: &mut Svg, area: Rect, track: &Track, lines: &[(&[Vec2], Color)]) {
+    let bounds = track.get_bounds();
+    let scale = (area.w / bounds.w).min(area.h / bounds.h) * 0.95;
+    let offset = area.center() - bounds.center() * scale;
+
+    svg.begin_group(offset, scale);
+    svg.path(track.get_points(), track.get_width(), TARMAC_COLOUR, true);
+    for i in 0..track.checkpoint_count() {
+        let (start, end) = track.get_checkpoint(i);
+        svg.line(start, end, 4.0, CHECKPOINT_COLOUR);
+    }
+    let points = track.get_points();
+    svg.circle((points[0] + points[1]) / 2.0, 8.0, WHITE);
+    for (line, colour) in lines.iter() {
+        svg.path(line, 4.0, *colour, false);
+    }
+    svg.end_group();
+}
+
+// the same chart as the in game graph, a line per series against generation
+pub fn draw_chart(
+    svg: &mut Svg,
+    area: Rect,
+    title: &str,
+    series: &[(&str, &[Option<f32>], Color)],
+) {
+    svg.outline(area, 1.0, GRAY);
+    svg.text(vec2(area.x, area.y - 8.0), 18.0, title, BLACK);
+    let mut legend_x = area.x + 140.0;
+    for (name, _, colour) in series.iter() {
+        svg.rect(Rect::new(legend_x, area.y - 18.0, 10.0, 10.0), *colour);
+        svg.text(vec2(legend_x + 14.0, area.y - 8.0), 14.0, name, BLACK);
+        legend_x += 80.0;
+    }
+
+    let Some(scale) = ChartScale::new(area, series) else {
+        svg.text(
+            vec2(area.x + 10.0, area.y + 25.0),
+            16.0,
+            "No data",
+            DARKGRAY,
+        );
+        return;
+    };
+    svg.text(
+        vec2(area.x - 45.0, area.y + 12.0),
+        13.0,
+        &scale.top_label(),
+        BLACK,
+    );
+    svg.text(
+        vec2(area.x - 45.0, area.y + area.h),
+        13.0,
+        &scale.bottom_label(),
+        BLACK,
+    );
+    svg.text(
+        vec2(area.x + area.w - 50.0, area.y + area.h + 15.0),
+        13.0,
+        &scale.last_generation_label(),
+        BLACK,
+    );
+
+    for (_, values, colour) in series.iter() {
+        // one polyline per unbroken stretch of values
+        let mut stretch: Vec<Vec2> = vec![];
+        for (i, value) in values.iter().enumerate() {
+            if let Some(value) = value {
+                stretch.push(scale.point(i, *value));
+            }
+            if value.is_none() || i + 1 == values.len() {
+                match stretch.len() {
+                    0 => {}
+                    1 => svg.circle(stretch[0], 2.5, *colour),
+                    _ => svg.path(&stretch, 2.0, *colour, false),
+                }
+                stretch.clear();
+            }
+        }
+    }
+}
+
+pub fn draw_fitness_charts(svg: &mut Svg, area: Rect, history: &[GenerationStats]) {
+    let best: Vec<Option<f32>> = history.iter().map(|s| Some(s.best as f32)).collect();
+    let mean: Vec<Option<f32>> = history.iter().map(|s| Some(s.mean as f32)).collect();
+    let worst: Vec<Option<f32>> = history.iter().map(|s| Some(s.worst as f32)).collect();
+    let laps: Vec<Option<f32>> = history.iter().map(|s| s.best_lap).collect();
+
+    let chart_height = (area.h - 90.0) / 2.0;
+    draw_chart(
+        svg,
+        Rect::new(area.x + 45.0, area.y + 30.0, area.w - 60.0, chart_height),
+        "Fitness",
+        &[
+            ("best", &best, BEST_COLOUR),
+            ("mean", &mean, MEAN_COLOUR),
+            ("worst", &worst, WORST_COLOUR),
+        ],
+    );
+    draw_chart(
+        svg,
+        Rect::new(
+            area.x + 45.0,
+            area.y + 75.0 + chart_height,
+            area.w - 60.0,
+            chart_height,
+        ),
+        "Best lap (s)",
+        &[("best lap", &laps, LAP_COLOUR)],
+    );
+}
+
+// the track with the run's fastest lap on the left and the learning curves on the right
+// the simulation writes one at the end of every generation, windowed or headless
+pub fn report(
+    title: &str,
+    track: &Track,
+    history: &[GenerationStats],
+    racing_line: &[Vec2],
+) -> Svg {
+    let mut svg = Svg::new(REPORT_WIDTH, REPORT_HEIGHT);
+    svg.text(vec2(20.0, 28.0), 22.0, title, BLACK);
+
+    let half = REPORT_WIDTH / 2.0;
+    let body = REPORT_HEIGHT - TITLE_HEIGHT;
+    draw_track(
+        &mut svg,
+        Rect::new(0.0, TITLE_HEIGHT, half, body),
+        track,
+        &[(racing_line, RACING_LINE_COLOUR)],
+    );
+    if racing_line.is_empty() {
+        svg.text(
+            vec2(20.0, REPORT_HEIGHT - 20.0),
+            16.0,
+            "No valid laps yet",
+            DARKGRAY,
+        );
+    }
+    draw_fitness_charts(&mut svg, Rect::new(half, TITLE_HEIGHT, half, body), history);
+    return svg;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn opacity_only_for_see_through_colours() {
+        assert_eq!(paint("fill", WHITE), "fill=\"rgb(255,255,255)\"");
+        let faded = Color::new(1.0, 0.0, 0.0, 0.5);
+        assert_eq!(
+            paint("stroke", faded),
+            "stroke=\"rgb(255,0,0)\" stroke-opacity=\"0.50\""
+        );
+    }
+
+    #[test]
+    fn text_is_escaped() {
+        let mut svg = Svg::new(10.0, 10.0);
+        svg.text(vec2(0.0, 0.0), 12.0, "a < b & c > d", BLACK);
+        let text = svg.to_text();
+        assert!(text.contains(">a &lt; b &amp; c &gt; d</text>"));
+        assert!(!text.contains("a < b"));
+    }
+
+    #[test]
+    fn chart_lines_break_at_gaps() {
+        let values = [
+            Some(1.0),
+            Some(2.0),
+            None,
+            Some(3.0),
+            None,
+            Some(4.0),
+            Some(5.0),
+        ];
+        let mut svg = Svg::new(200.0, 200.0);
+        draw_chart(
+            &mut svg,
+            Rect::new(50.0, 50.0, 100.0, 100.0),
+            "Test",
+            &[("values", &values, BLUE)],
+        );
+        let text = svg.to_text();
+        // two stretches of two points, and the lone value between them as a dot
+        assert_eq!(text.matches("<polyline").count(), 2);
+        assert_eq!(text.matches("<circle").count(), 1);
+    }
+
+    #[test]
+    fn report_without_history() {
+        let track = Track::from_id("test_track1").unwrap();
+        let text = report("Empty run", &track, &[], &[]).to_text();
+        assert!(text.starts_with("<svg "));
+        assert!(text.ends_with("</svg>\n"));
+        assert!(text.contains(">Empty run</text>"));
+        assert!(text.contains(">No valid laps yet</text>"));
+        assert_eq!(text.matches(">No data</text>").count(), 2);
+        // only the track is drawn, with its start dot and an empty racing line left out
+        assert_eq!(text.matches("<polyline").count(), 0);
+        assert_eq!(text.matches("<polygon").count(), 1);
+        assert_eq!(text.matches("<circle").count(), 1);
+    }
+}
@@ -0,0 +1,297 @@
+use crate::physics::Controls;
+use crate::stats::RUNS_DIR;
+use macroquad::prelude::*;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+// the folder inside a run's folder holding its laps
+pub const TELEMETRY_DIR: &str = "telemetry";
+const FIXED_COLUMNS: &str =
+    "time,distance,x,y,vx,vy,speed,angle,steer,throttle,brake,sector,fitness_delta";
+
+// which cars have their laps logged tick by tick
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum TelemetryMode {
+    Off,
+    FastestLaps, // the leading car records, its laps that go top of the leaderboard are kept
+    Selected,    // only cars clicked on during the generation, every full lap is kept
+}
+
+// everything about a car on one tick
+#[derive(Clone)]
+pub struct TelemetryFrame {
+    pub time: f32,     // seconds into the lap
+    pub distance: f32, // driven since the start of the lap
+    pub position: Vec2,
+    pub velocity: Vec2,
+    pub angle: f32,
+    pub controls: Controls,
+    pub sector: usize,
+    pub fitness_delta: i32, // fitness gained on this tick
+    pub rays: Vec<f32>,     // wall distances in pixels
+}
+
+// one lap of frames and where it came from
+pub struct TelemetryLap {
+    pub name: String, // e.g. gen0003_car012_lap2
+    pub run: String,  // the run folder, empty if the lap isn't saved yet
+    pub lap_seconds: f32,
+    pub invalid: bool,
+    pub frames: Vec<TelemetryFrame>,
+}
+
+impl TelemetryFrame {
+    pub fn speed(&self) -> f32 {
+        return self.velocity.length();
+    }
+
+    fn to_line(&self) -> String {
+        let mut line = format!(
+            "{:.4},{:.2},{:.2},{:.2},{:.3},{:.3},{:.3},{:.4},{:.3},{:.3},{:.3},{},{}",
+            self.time,
+            self.distance,
+            self.position.x,
+            self.position.y,
+            self.velocity.x,
+            self.velocity.y,
+            self.speed(),
+            self.angle,
+            self.controls.steer,
+            self.controls.throttle,
+            self.controls.brake,
+            self.sector,
+            self.fitness_delta
+        );
+        for ray in self.rays.iter() {
+            line += &format!(",{:.1}", ray);
+        }
+        return line;
+    }
+
+    fn from_line(line: &str) -> Option<Self> {
+        let parts: Vec<&str> = line.split(',').collect();
+        if parts.len() < 13 {
+            return None;
+        }
+        let number = |i: usize| -> Option<f32> { parts[i].parse().ok() };
+        Some(Self {
+            time: number(0)?,
+            distance: number(1)?,
+            position: vec2(number(2)?, number(3)?),
+            velocity: vec2(number(4)?, number(5)?),
+            // 6 is the speed, worked out again from the velocity
+            angle: number(7)?,
+            controls: Controls {
+                steer: number(8)?,
+                throttle: number(9)?,
+                brake: number(10)?,
+            },
+            sector: parts[11].parse().ok()?,
+            fitness_delta: parts[12].parse().ok()?,
+            rays: parts[13..]
+                .iter()
+                .map(|r| r.parse().ok())
+                .collect::<Option<_>>()?,
+        })
+    }
+}
+
+impl TelemetryLap {
+    pub fn new(
+        generation: usize,
+        car_number: usize,
+        lap: usize,
+        invalid: bool,
+        frames: Vec<TelemetryFrame>,
+    ) -> Self {
+        Self {
+            name: format!("gen{:04}_car{:03}_lap{}", generation, car_number, lap),
+            run: String::new(),
+            lap_seconds: frames.last().map_or(0.0, |f| f.time),
+            invalid,
+            frames,
+        }
+    }
+
+    pub fn get_length(&self) -> f32 {
+        return self.frames.last().map_or(0.0, |f| f.distance);
+    }
+
+    // the last frame at or before a distance into the lap
+    pub fn frame_at(&self, distance: f32) -> Option<&TelemetryFrame> {
+        let i = self.frames.partition_point(|f| f.distance <= distance);
+        return self.frames.get(i.saturating_sub(1));
+    }
+
+    // saved as <run folder>/telemetry/<name>.csv, the first line says if the lap counted
+    pub fn save(&self, run_dir: &str) {
+        let dir = format!("{}/{}", run_dir, TELEMETRY_DIR);
+        let path = format!("{}/{}.csv", dir, self.name);
+
+        let ray_count = self.frames.first().map_or(0, |f| f.rays.len());
+        let mut text = format!("# lap {:.3} invalid {}\n", self.lap_seconds, self.invalid);
+        text += FIXED_COLUMNS;
+        for i in 0..ray_count {
+            text += &format!(",ray{}", i);
+        }
+        text += "\n";
+        for frame in self.frames.iter() {
+            text += &frame.to_line();
+            text += "\n";
+        }
+
+        let result = fs::create_dir_all(&dir).and_then(|_| fs::write(&path, text));
+        if let Err(e) = result {
+            println!("[telemetry.rs] - Couldn't save telemetry {}: {}", path, e);
+        }
+    }
+
+    pub fn load(path: &Path) -> Option<Self> {
+        let text = fs::read_to_string(path).ok()?;
+        let mut lines = text.lines();
+
+        let info: Vec<&str> = lines.next()?.split_whitespace().collect();
+        let ["#", "lap", lap_seconds, "invalid", invalid] = info[..] else {
+            return None;
+        };
+        lines.next()?; // the column names
+
+        let mut frames = vec![];
+        for line in lines {
+            match TelemetryFrame::from_line(line) {
+                Some(frame) => frames.push(frame),
+                None => {
+                    println!("[telemetry.rs] - Bad frame in {}", path.display());
+                    return None;
+                }
+            }
+        }
+
+        // the run is the folder above the telemetry folder
+        let run = path
+            .parent()
+            .and_then(|p| p.parent())
+            .and_then(|p| p.file_name())
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_default();
+        Some(Self {
+            name: path.file_stem()?.to_string_lossy().to_string(),
+            run,
+            lap_seconds: lap_seconds.parse().ok()?,
+            invalid: invalid == "true",
+            frames,
+        })
+    }
+}
+
+// every saved lap from every run, oldest first
+pub fn list_laps() -> Vec<PathBuf> {
+    let runs = fs::read_dir(RUNS_DIR).into_iter().flatten().flatten();
+    let mut files: Vec<(std::time::SystemTime, PathBuf)> = runs
+        .filter_map(|run| fs::read_dir(run.path().join(TELEMETRY_DIR)).ok())
+        .flat_map(|laps| laps.flatten())
+        .map(|e| e.path())
+        .filter(|p| p.extension().is_some_and(|ext| ext == "csv"))
+        .filter_map(|p| Some((fs::metadata(&p).ok()?.modified().ok()?, p)))
+        .collect();
+    files.sort();
+    return files.into_iter().map(|(_, p)| p).collect();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // values that survive the rounding in to_line
+    fn frame(time: f32, distance: f32) -> TelemetryFrame {
+        return TelemetryFrame {
+            time,
+            distance,
+            position: vec2(120.5, -40.25),
+            velocity: vec2(3.0, -4.0),
+            angle: 1.5,
+            controls: Controls {
+                throttle: 0.75,
+                steer: -0.5,
+                brake: 0.0,
+            },
+            sector: 3,
+            fitness_delta: -12,
+            rays: vec![10.5, 200.0, 0.0],
+        };
+    }
+
+    fn lap(distances: &[f32]) -> TelemetryLap {
+        let frames = distances
+            .iter()
+            .enumerate()
+            .map(|(i, d)| frame(i as f32 * 0.5, *d))
+            .collect();
+        return TelemetryLap::new(2, 7, 1, false, frames);
+    }
+
+    #[test]
+    fn frame_line_loads_back() {
+        let original = frame(12.25, 345.5);
+        let line = original.to_line();
+        let loaded = TelemetryFrame::from_line(&line).unwrap();
+        assert_eq!(loaded.time, original.time);
+        assert_eq!(loaded.distance, original.distance);
+        assert_eq!(loaded.position, original.position);
+        assert_eq!(loaded.velocity, original.velocity);
+        assert_eq!(loaded.speed(), 5.0);
+        assert_eq!(loaded.angle, original.angle);
+        assert_eq!(loaded.controls.throttle, original.controls.throttle);
+        assert_eq!(loaded.controls.steer, original.controls.steer);
+        assert_eq!(loaded.controls.brake, original.controls.brake);
+        assert_eq!(loaded.sector, original.sector);
+        assert_eq!(loaded.fitness_delta, original.fitness_delta);
+        assert_eq!(loaded.rays, original.rays);
+        assert_eq!(loaded.to_line(), line);
+    }
+
+    #[test]
+    fn frame_without_rays_loads_back() {
+        let mut original = frame(0.0, 0.0);
+        original.rays.clear();
+        let loaded = TelemetryFrame::from_line(&original.to_line()).unwrap();
+        assert!(loaded.rays.is_empty());
+    }
+
+    #[test]
+    fn bad_frame_lines_are_rejected() {
+        let line = frame(1.0, 2.0).to_line();
+        assert!(TelemetryFrame::from_line("").is_none());
+        // short of the fixed columns
+        let short: Vec<&str> = line.split(',').take(12).collect();
+        assert!(TelemetryFrame::from_line(&short.join(",")).is_none());
+        assert!(TelemetryFrame::from_line(&line.replacen("1.0000", "x", 1)).is_none());
+        assert!(TelemetryFrame::from_line(&format!("{},ray", line)).is_none());
+    }
+
+    #[test]
+    fn frame_at_finds_the_last_frame_reached() {
+        let lap = lap(&[0.0, 10.0, 20.0, 30.0]);
+        assert_eq!(lap.get_length(), 30.0);
+        assert_eq!(lap.lap_seconds, 1.5);
+        assert_eq!(lap.frame_at(0.0).unwrap().distance, 0.0);
+        assert_eq!(lap.frame_at(15.0).unwrap().distance, 10.0);
+        assert_eq!(lap.frame_at(20.0).unwrap().distance, 20.0);
+        // past the end stays on the last frame
+        assert_eq!(lap.frame_at(99.0).unwrap().distance, 30.0);
+    }
+
+    #[test]
+    fn frame_at_before_the_start_is_the_first_frame() {
+        let lap = lap(&[5.0, 10.0]);
+        assert_eq!(lap.frame_at(1.0).unwrap().distance, 5.0);
+        assert!(TelemetryLap::new(0, 1, 1, false, vec![])
+            .frame_at(1.0)
+            .is_none());
+    }
+
+    #[test]
+    fn lap_name_says_where_it_came_from() {
+        assert_eq!(lap(&[0.0]).name, "gen0002_car007_lap1");
+    }
+}
@@ -0,0 +1,317 @@
+use crate::telemetry::*;
+use macroquad::prelude::*;
+use std::path::PathBuf;
+
+const ROWS_SHOWN: usize = 22;
+const ROW_HEIGHT: f32 = 26.0;
+const LIST_TOP: f32 = 150.0;
+const LIST_X: f32 = 40.0;
+const LIST_WIDTH: f32 = 420.0;
+
+const CHART_X: f32 = 520.0;
+const CHART_WIDTH: f32 = 480.0; // leaves room for the end button
+const CHART_HEIGHT: f32 = 110.0;
+const CHART_TOP: f32 = 150.0;
+const CHART_GAP: f32 = 40.0;
+
+const LAP_A_COLOUR: Color = color_u8!(30, 90, 220, 255);
+const LAP_B_COLOUR: Color = color_u8!(220, 40, 40, 255);
+
+// what each chart shows and the range it's drawn over, None means fit to the laps
+struct Channel {
+    name: &'static str,
+    value: fn(&TelemetryFrame) -> f32,
+    range: Option<(f32, f32)>,
+}
+
+const CHANNELS: [Channel; 4] = [
+    Channel {
+        name: "Speed",
+        value: |f| f.speed(),
+        range: None,
+    },
+    Channel {
+        name: "Throttle",
+        value: |f| f.controls.throttle,
+        range: Some((0.0, 1.0)),
+    },
+    Channel {
+        name: "Brake",
+        value: |f| f.controls.brake,
+        range: Some((0.0, 1.0)),
+    },
+    Channel {
+        name: "Steer",
+        value: |f| f.controls.steer,
+        range: Some((-1.0, 1.0)),
+    },
+];
+
+// main menu screen plotting saved laps against distance, like a motorsport data trace
+// left click a lap to show it in blue, right click to compare it in red
+pub struct TelemetryViewer {
+    files: Vec<PathBuf>, // newest first
+    lap_a: Option<(usize, TelemetryLap)>,
+    lap_b: Option<(usize, TelemetryLap)>,
+}
+
+impl Default for TelemetryViewer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl TelemetryViewer {
+    pub fn new() -> Self {
+        let mut files = list_laps();
+        files.reverse();
+        files.truncate(ROWS_SHOWN);
+        let lap_a = files
+            .first()
+            .and_then(|path| TelemetryLap::load(path))
+            .map(|lap| (0, lap));
+
+        Self {
+            files,
+            lap_a,
+            lap_b: None,
+        }
+    }
+
+    fn open(&self, row: usize) -> Option<(usize, TelemetryLap)> {
+        let path = &self.files[row];
+        match TelemetryLap::load(path) {
+            Some(lap) => return Some((row, lap)),
+            None => {
+                println!("[telemetry_viewer.rs] - Couldn't read {}", path.display());
+                return None;
+            }
+        }
+    }
+
+    pub fn update(&mut self) {
+        let (x, y) = mouse_position();
+        let row = ((y - LIST_TOP) / ROW_HEIGHT).floor();
+        if !(LIST_X..LIST_X + LIST_WIDTH).contains(&x) || row < 0.0 {
+            return;
+        }
+        let row = row as usize;
+        if row >= self.files.len() {
+            return;
+        }
+
+        if is_mouse_button_pressed(MouseButton::Left) {
+            self.lap_a = self.open(row);
+        }
+        if is_mouse_button_pressed(MouseButton::Right) {
+            // right clicking the compared lap again hides it
+            self.lap_b = match self.lap_b.as_ref().is_some_and(|(i, _)| *i == row) {
+                true => None,
+                false => self.open(row),
+            };
+        }
+    }
+
+    fn shown_laps(&self) -> Vec<(&TelemetryLap, Color)> {
+        let mut laps = vec![];
+        if let Some((_, lap)) = &self.lap_a {
+            laps.push((lap, LAP_A_COLOUR));
+        }
+        if let Some((_, lap)) = &self.lap_b {
+            laps.push((lap, LAP_B_COLOUR));
+        }
+        return laps;
+    }
+
+    pub fn draw(&self) {
+        draw_text("Telemetry", LIST_X, 80.0, 60.0, BLACK);
+        draw_text(
+            "Left click a lap to view it, right click another to compare",
+            LIST_X,
+            110.0,
+            22.0,
+            DARKGRAY,
+        );
+
+        self.draw_list();
+
+        let laps = self.shown_laps();
+        if laps.is_empty() {
+            draw_text(
+                "Turn telemetry on in the menu to log laps",
+                CHART_X,
+                CHART_TOP + 25.0,
+                25.0,
+                DARKGRAY,
+            );
+            return;
+        }
+
+        // the longer lap sets the distance axis so both fit
+        let length = laps
+            .iter()
+            .map(|(lap, _)| lap.get_length())
+            .fold(1.0, f32::max);
+        for (i, channel) in CHANNELS.iter().enumerate() {
+            let area = Rect::new(
+                CHART_X,
+                CHART_TOP + i as f32 * (CHART_HEIGHT + CHART_GAP),
+                CHART_WIDTH,
+                CHART_HEIGHT,
+            );
+            draw_channel(area, channel, &laps, length);
+        }
+        self.draw_cursor(&laps, length);
+        self.draw_summary(&laps);
+    }
+
+    fn draw_list(&self) {
+        if self.files.is_empty() {
+            draw_text(
+                "No laps logged yet",
+                LIST_X,
+                LIST_TOP + 20.0,
+                25.0,
+                DARKGRAY,
+            );
+        }
+        for (i, path) in self.files.iter().enumerate() {
+            let y = LIST_TOP + ROW_HEIGHT * i as f32;
+            if self.lap_a.as_ref().is_some_and(|(row, _)| *row == i) {
+                let colour = Color {
+                    a: 0.35,
+                    ..LAP_A_COLOUR
+                };
+                draw_rectangle(LIST_X, y, LIST_WIDTH, ROW_HEIGHT, colour);
+            }
+            if self.lap_b.as_ref().is_some_and(|(row, _)| *row == i) {
+                let colour = Color {
+                    a: 0.35,
+                    ..LAP_B_COLOUR
+                };
+                draw_rectangle(LIST_X, y, LIST_WIDTH, ROW_HEIGHT, colour);
+            }
+
+            // the run folder starts with the date and time it began
+            let run = path
+                .parent()
+                .and_then(|p| p.parent())
+                .and_then(|p| p.file_name())
+                .map(|n| n.to_string_lossy().to_string())
+                .unwrap_or_default();
+            let name = path
+                .file_stem()
+                .map(|n| n.to_string_lossy().to_string())
+                .unwrap_or_default();
+            let started: String = run.chars().take(16).collect();
+            let text = format!("{}  {}", started, name);
+            draw_text(&text, LIST_X + 5.0, y + ROW_HEIGHT * 0.75, 20.0, BLACK);
+        }
+    }
+
+    // a line down every chart at the mouse with the values there
+    fn draw_cursor(&self, laps: &[(&TelemetryLap, Color)], length: f32) {
+        let (x, y) = mouse_position();
+        let bottom = CHART_TOP + CHANNELS.len() as f32 * (CHART_HEIGHT + CHART_GAP);
+        if !(CHART_X..CHART_X + CHART_WIDTH).contains(&x) || !(CHART_TOP..bottom).contains(&y) {
+            return;
+        }
+        let distance = (x - CHART_X) / CHART_WIDTH * length;
+        draw_line(x, CHART_TOP, x, bottom - CHART_GAP, 1.0, DARKGRAY);
+
+        for (i, channel) in CHANNELS.iter().enumerate() {
+            let top = CHART_TOP + i as f32 * (CHART_HEIGHT + CHART_GAP);
+            for (j, (lap, colour)) in laps.iter().enumerate() {
+                if let Some(frame) = lap.frame_at(distance) {
+                    let text = format!("{:.2}", (channel.value)(frame));
+                    draw_text(
+                        &text,
+                        CHART_X + CHART_WIDTH - 120.0 + j as f32 * 60.0,
+                        top - 8.0,
+                        18.0,
+                        *colour,
+                    );
+                }
+            }
+        }
+        draw_text(
+            &format!("{:.0} px into the lap", distance),
+            CHART_X,
+            bottom - 15.0,
+            18.0,
+            BLACK,
+        );
+    }
+
+    fn draw_summary(&self, laps: &[(&TelemetryLap, Color)]) {
+        for (i, (lap, colour)) in laps.iter().enumerate() {
+            let invalid = match lap.invalid {
+                true => " (invalid)",
+                false => "",
+            };
+            let text = format!(
+                "{} {}  {:.3}s{}",
+                lap.run, lap.name, lap.lap_seconds, invalid
+            );
+            draw_text(&text, CHART_X, 60.0 + i as f32 * 25.0, 22.0, *colour);
+        }
+        if let [(a, _), (b, _)] = laps {
+            let delta = b.lap_seconds - a.lap_seconds;
+            let text = format!("Red is {:+.3}s on blue", delta);
+            draw_text(&text, CHART_X, 120.0, 22.0, BLACK);
+        }
+    }
+}
+
+fn draw_channel(area: Rect, channel: &Channel, laps: &[(&TelemetryLap, Color)], length: f32) {
+    let (min, max) = channel.range.unwrap_or_else(|| {
+        let top = laps
+            .iter()
+            .flat_map(|(lap, _)| lap.frames.iter().map(channel.value))
+            .fold(1.0, f32::max);
+        (0.0, top)
+    });
+
+    draw_rectangle(
+        area.x,
+        area.y,
+        area.w,
+        area.h,
+        color_u8!(255, 255, 255, 180),
+    );
+    draw_rectangle_lines(area.x, area.y, area.w, area.h, 1.0, GRAY);
+    draw_text(channel.name, area.x, area.y - 8.0, 20.0, BLACK);
+    draw_text(
+        &format!("{:.1}", max),
+        area.x - 45.0,
+        area.y + 12.0,
+        16.0,
+        BLACK,
+    );
+    draw_text(
+        &format!("{:.1}", min),
+        area.x - 45.0,
+        area.y + area.h,
+        16.0,
+        BLACK,
+    );
+    if min < 0.0 && max > 0.0 {
+        let zero = area.y + area.h * max / (max - min);
+        draw_line(area.x, zero, area.x + area.w, zero, 1.0, LIGHTGRAY);
+    }
+
+    let point = |frame: &TelemetryFrame| -> Vec2 {
+        let fraction_y = ((channel.value)(frame) - min) / (max - min);
+        return vec2(
+            area.x + frame.distance / length * area.w,
+            area.y + area.h - clamp(fraction_y, 0.0, 1.0) * area.h,
+        );
+    };
+    for (lap, colour) in laps.iter() {
+        for pair in lap.frames.windows(2) {
+            let a = point(&pair[0]);
+            let b = point(&pair[1]);
+            draw_line(a.x, a.y, b.x, b.y, 1.5, *colour);
+        }
+    }
+}
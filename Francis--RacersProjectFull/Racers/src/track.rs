@@ -1,10 +1,13 @@
+use crate::geometry::point_in_polygon;
+use crate::spatial::SegmentGrid;
 use crate::utils::draw_thick_line;
 use macroquad::prelude::*;
 
-pub const tarmac_colour: Color = color_u8!(171, 170, 167, 255);
-pub const checkpoint_colour: Color = color_u8!(36, 255, 251, 150);
+pub const TARMAC_COLOUR: Color = color_u8!(171, 170, 167, 255);
+pub const CHECKPOINT_COLOUR: Color = color_u8!(36, 255, 251, 150);
+pub const RACING_LINE_COLOUR: Color = color_u8!(255, 140, 0, 255);
 
-pub const test_track1: [Vec2; 20] = [
+pub const TEST_TRACK1: [Vec2; 20] = [
     vec2(507.0, 142.0),
     vec2(654.0, 140.0),
     vec2(782.0, 139.0),
@@ -28,18 +31,91 @@ pub const test_track1: [Vec2; 20] = [
 ];
 
 pub struct Track {
-    points_set: [Vec2; 20],
+    pub id: String, // used to store lap records and ghosts per track
+    points_set: Vec<Vec2>,
     track_width: f32,
+
+    // precomputed edges of the track, one point per centre line point
+    left_boundary: Vec<Vec2>,
+    right_boundary: Vec<Vec2>,
+    boundary_grid: SegmentGrid,
+    boundary_normals: Vec<Vec2>, // points onto the tarmac, one per grid segment
+
+    // the fastest lap driven here, drawn on top of the tarmac
+    racing_line: Vec<Vec2>,
 }
 
 impl Track {
-    pub fn new(points_set: [Vec2; 20], track_width: f32) -> Self {
+    pub fn new(id: &str, points_set: &[Vec2], track_width: f32) -> Self {
+        let points_set = points_set.to_vec();
+        let (left_boundary, right_boundary) = Self::build_boundaries(&points_set, track_width);
+
+        // every boundary edge goes in the grid, left edges first then right edges
+        let mut segments = vec![];
+        for boundary in [&left_boundary, &right_boundary] {
+            for i in 0..boundary.len() {
+                segments.push((boundary[i], boundary[(i + 1) % boundary.len()]));
+            }
+        }
+        // the centre line is always on the tarmac side of an edge
+        let mut boundary_normals = vec![];
+        for (i, (a, b)) in segments.iter().enumerate() {
+            let n = points_set.len();
+            let centre = (points_set[i % n] + points_set[(i + 1) % n]) / 2.0;
+            let mut normal = (*b - *a).perp().normalize_or_zero();
+            if normal.dot(centre - (*a + *b) / 2.0) < 0.0 {
+                normal = -normal;
+            }
+            boundary_normals.push(normal);
+        }
+
+        // cells roughly the size of the track width keep each cell down to a few edges
+        let boundary_grid = SegmentGrid::new(segments, track_width);
+
         return Self {
+            id: id.to_string(),
             points_set,
             track_width,
+            left_boundary,
+            right_boundary,
+            boundary_grid,
+            boundary_normals,
+            racing_line: vec![],
         };
     }
 
+    fn build_boundaries(points: &[Vec2], track_width: f32) -> (Vec<Vec2>, Vec<Vec2>) {
+        let mut left = vec![];
+        let mut right = vec![];
+        let n = points.len();
+
+        for i in 0..n {
+            let prev = points[(i + n - 1) % n];
+            let point = points[i];
+            let next = points[(i + 1) % n];
+
+            // average the normals of the two segments meeting at this point
+            let direction1 = point - prev;
+            let direction2 = next - point;
+            let normal1 = vec2(-direction1.y, direction1.x);
+            let normal2 = vec2(-direction2.y, direction2.x);
+            let avg_normal = ((normal1 + normal2) / 2.0).normalize_or_zero();
+
+            left.push(point + avg_normal * (track_width / 2.0));
+            right.push(point - avg_normal * (track_width / 2.0));
+        }
+
+        (left, right)
+    }
+
+    // the tracks that can be looked up by id, e.g. from a saved replay
+    pub fn from_id(id: &str) -> Option<Self> {
+        match id {
+            "test_track1" => Some(Self::new(id, &TEST_TRACK1, 100.0)),
+            _ => None,
+        }
+    }
+
     pub fn draw(&self) {
         for i in 0..self.points_set.len() {
             let p1 = self.points_set[i];
@@ -47,48 +123,104 @@ impl Track {
 
             let mp = (p1 + p2) / 2.0;
 
-            draw_thick_line(p1.x, p1.y, p2.x, p2.y, self.track_width, tarmac_colour);
+            draw_thick_line(p1.x, p1.y, p2.x, p2.y, self.track_width, TARMAC_COLOUR);
             if i == 0 {
                 draw_circle(mp.x, mp.y, 8.0, WHITE);
             }
         }
         // draw the checkpoints
         self.draw_checkpoints();
+
+        for pair in self.racing_line.windows(2) {
+            draw_line(
+                pair[0].x,
+                pair[0].y,
+                pair[1].x,
+                pair[1].y,
+                3.0,
+                RACING_LINE_COLOUR,
+            );
+        }
     }
 
-    pub fn get_points(&self) -> &[Vec2; 20] {
+    pub fn set_racing_line(&mut self, line: Vec<Vec2>) {
+        self.racing_line = line;
+    }
+
+    pub fn get_racing_line(&self) -> &[Vec2] {
+        return &self.racing_line;
+    }
+
+    pub fn get_points(&self) -> &[Vec2] {
         return &self.points_set;
     }
 
-    pub fn get_width(&self) -> f32 {
-        return self.track_width;
+    pub fn get_left_boundary(&self) -> &[Vec2] {
+        return &self.left_boundary;
     }
 
-    pub fn draw_checkpoints(&self) {
-        for i in 0..self.points_set.len() {
-            let p1 = self.points_set[i];
-            let p2 = self.points_set[(i + 1) % self.points_set.len()]; // this is the one we drawing on
-            let p3 = self.points_set[(i + 2) % self.points_set.len()];
+    pub fn get_right_boundary(&self) -> &[Vec2] {
+        return &self.right_boundary;
+    }
 
-            let joining_vec1 = p2 - p1;
-            let normal1 = vec2(-joining_vec1.y, joining_vec1.x);
+    pub fn contains_point(&self, point: Vec2) -> bool {
+        // one boundary loop sits inside the other, so the tarmac is the area inside
+        // exactly one of them
+        return point_in_polygon(point, &self.left_boundary)
+            != point_in_polygon(point, &self.right_boundary);
+    }
 
-            let joining_vec2 = p3 - p2;
-            let normal2 = vec2(-joining_vec2.y, joining_vec2.x);
+    pub fn nearest_boundary(&self, point: Vec2) -> (Vec2, Vec2) {
+        // closest point on any track edge and that edge's normal pointing onto the tarmac
+        match self.boundary_grid.nearest(point) {
+            Some((closest, i)) => return (closest, self.boundary_normals[i]),
+            None => return (point, Vec2::ZERO),
+        }
+    }
 
-            let avg_normal = (normal1 + normal2) / 2.0;
+    pub fn cast_ray(&self, origin: Vec2, direction: Vec2, max_distance: f32) -> f32 {
+        // distance to the nearest track edge along the ray, capped at max_distance
+        match self.boundary_grid.cast_ray(origin, direction, max_distance) {
+            Some((distance, _)) => distance,
+            None => max_distance,
+        }
+    }
 
-            let checkpoint_vec = avg_normal.normalize() * self.track_width;
-            let start_pos = p2;
+    pub fn get_bounds(&self) -> Rect {
+        // smallest rectangle holding both edges of the track
+        let mut min = Vec2::splat(f32::MAX);
+        let mut max = Vec2::splat(f32::MIN);
+        for point in self.left_boundary.iter().chain(self.right_boundary.iter()) {
+            min = min.min(*point);
+            max = max.max(*point);
+        }
+        return Rect::new(min.x, min.y, max.x - min.x, max.y - min.y);
+    }
 
-            draw_line(
-                start_pos.x - checkpoint_vec.x * 0.5,
-                start_pos.y - checkpoint_vec.y * 0.5,
-                start_pos.x + checkpoint_vec.x * 0.5,
-                start_pos.y + checkpoint_vec.y * 0.5,
-                4.0,
-                checkpoint_colour,
-            );
+    pub fn get_width(&self) -> f32 {
+        return self.track_width;
+    }
+
+    pub fn checkpoint_count(&self) -> usize {
+        return self.points_set.len();
+    }
+
+    // the line across the track at centre line point i, checkpoint 0 is the finish line
+    pub fn get_checkpoint(&self, i: usize) -> (Vec2, Vec2) {
+        return (self.left_boundary[i], self.right_boundary[i]);
+    }
+
+    // which way the cars should cross checkpoint i
+    pub fn get_checkpoint_direction(&self, i: usize) -> Vec2 {
+        let n = self.points_set.len();
+        return (self.points_set[(i + 1) % n] - self.points_set[(i + n - 1) % n])
+            .normalize_or_zero();
+    }
+
+    pub fn draw_checkpoints(&self) {
+        for i in 0..self.checkpoint_count() {
+            let (start, end) = self.get_checkpoint(i);
+            draw_line(start.x, start.y, end.x, end.y, 4.0, CHECKPOINT_COLOUR);
         }
     }
 
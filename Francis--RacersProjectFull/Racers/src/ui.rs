@@ -7,6 +7,14 @@ pub struct Button {
     text: String,
 }
 
+// a button that cycles through a list of options each time it is clicked
+pub struct Selector {
+    pub index: usize,
+    hitbox: Rect,
+    options: Vec<String>,
+    default: usize,
+}
+
 pub struct Slider {
     pub value: i32,
     min: i32,
@@ -46,11 +54,53 @@ impl Button {
         return false;
     }
 
+    pub fn set_position(&mut self, x: f32, y: f32) {
+        self.hitbox.x = x;
+        self.hitbox.y = y;
+    }
+
     pub fn reset(&mut self) {
         self.pressed = false;
     }
 }
 
+impl Selector {
+    pub fn new(x: f32, y: f32, width: f32, height: f32, options: &[&str], default: usize) -> Self {
+        Self {
+            hitbox: Rect::new(x, y, width, height),
+            options: options.iter().map(|o| o.to_string()).collect(),
+            index: default,
+            default,
+        }
+    }
+
+    pub fn draw(&self) {
+        let r = self.hitbox;
+        draw_rectangle(r.x, r.y, r.w, r.h, LIGHTGRAY);
+        draw_rectangle_lines(r.x, r.y, r.w, r.h, 3.0, BLACK);
+        draw_text(
+            &format!("< {} >", self.options[self.index]),
+            r.x + 10.0,
+            r.y + r.h * 0.7,
+            25.0,
+            BLACK,
+        );
+    }
+
+    pub fn update(&mut self) {
+        if is_mouse_button_pressed(MouseButton::Left) {
+            let mp = mouse_position();
+            if self.hitbox.contains(mp.into()) {
+                self.index = (self.index + 1) % self.options.len();
+            }
+        }
+    }
+
+    pub fn reset(&mut self) {
+        self.index = self.default;
+    }
+}
+
 impl Slider {
     pub fn new(x: f32, y: f32, min: i32, max: i32, default: i32) -> Self {
         Self {
@@ -71,7 +121,7 @@ impl Slider {
         draw_line(pos.x, pos.y, pos.x + width, pos.y, 6.0, BLACK);
         let node_pos = Vec2::new(
             pos.x
-                + width as f32 * (self.value as f32 - self.min as f32)
+                + width * (self.value as f32 - self.min as f32)
                     / (self.max as f32 - self.min as f32),
             pos.y,
         );
@@ -80,7 +130,7 @@ impl Slider {
         draw_text(&format!("{}", self.min), pos.x - 25.0, pos.y, 20.0, BLACK);
         draw_text(
             &format!("{}", self.max),
-            pos.x + width as f32 + 15.0,
+            pos.x + width + 15.0,
             pos.y,
             20.0,
             BLACK,
@@ -100,16 +150,17 @@ impl Slider {
         let width = 400.0;
         let node_pos = Vec2::new(
             pos.x
-                + width as f32 * (self.value as f32 - self.min as f32)
+                + width * (self.value as f32 - self.min as f32)
                     / (self.max as f32 - self.min as f32),
             pos.y,
         );
         let mp = Vec2::from(mouse_position());
 
-        if !self.selected && is_mouse_button_down(MouseButton::Left) {
-            if (mp - node_pos).length() <= radius {
-                self.selected = true;
-            }
+        if !self.selected
+            && is_mouse_button_down(MouseButton::Left)
+            && (mp - node_pos).length() <= radius
+        {
+            self.selected = true;
         }
 
         if (self.selected && is_mouse_button_released(MouseButton::Left)) {
@@ -119,7 +170,7 @@ impl Slider {
         if (self.selected) {
             // make node follow the mouse's x position
             // how many pixels is one value point worth
-            let pixels_per_unit: f32 = width as f32 / (self.max as f32 - self.min as f32);
+            let pixels_per_unit: f32 = width / (self.max as f32 - self.min as f32);
 
             let dx = mp.x - node_pos.x;
             let delta_value = dx / pixels_per_unit;
@@ -27,35 +27,36 @@ pub fn draw_thick_line(x1: f32, y1: f32, x2: f32, y2: f32, thickness: f32, colou
     }
 }
 
-pub fn find_line_eq(x1: f32, y1: f32, x2: f32, y2: f32) -> Vec2 {
-    // trying to complete form:
-    // ax + by + c = 0
-    let m = (y2 - y1) / (x2 - x1);
-    let d = y1 - m * x1;
-    let a = -m;
-    let c = -d;
-
-    // the Vec2 will return (a, c) in this form
-    return vec2(a, c);
-}
-
-pub fn line_intersection(p1: Vec2, p2: Vec2, q1: Vec2, q2: Vec2) -> Option<Vec2> {
-    let t = ((p1.x - q1.x) * (q1.y - q2.y) - (p1.y - q1.y) * (q1.x - q2.x))
-        / ((p1.x - p2.x) * (q1.y - q2.y) - (p1.y - p2.y) * (q1.x - q2.x));
-
-    let u = -(((p1.x - p2.x) * (p1.y - q1.y) - (p1.y - p2.y) * (p1.x - q1.x))
-        / ((p1.x - p2.x) * (q1.y - q2.y) - (p1.y - p2.y) * (q1.x - q2.x)));
-
-    if (0.0 <= t && t <= 1.0) && (0.0 <= u && u <= 1.0) {
-        let point_intersection = vec2(q1.x + u * (q2.x - q1.x), q1.y + u * (q2.y - q1.y));
-        return Some(point_intersection);
+pub fn repeat_space(n: usize) {
+    for _ in 0..n {
+        print!(" ");
     }
+}
 
-    None
+pub fn format_date(unix_seconds: u64) -> String {
+    // turns seconds since 1970 into year-month-day
+    // (Howard Hinnant's days to civil date algorithm)
+    let days = (unix_seconds / 86400) as i64 + 719468;
+    let era = days.div_euclid(146097);
+    let day_of_era = days.rem_euclid(146097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = year_of_era + era * 400 + (month <= 2) as i64;
+    return format!("{}-{:02}-{:02}", year, month, day);
 }
 
-pub fn repeat_space(n: usize) {
-    for i in 0..n {
-        print!(" ");
-    }
+pub fn format_date_time(unix_seconds: u64) -> String {
+    // e.g. 2024-05-01_13-45-09, safe to use in a file name
+    let seconds = unix_seconds % 86400;
+    return format!(
+        "{}_{:02}-{:02}-{:02}",
+        format_date(unix_seconds),
+        seconds / 3600,
+        (seconds / 60) % 60,
+        seconds % 60
+    );
 }